@@ -0,0 +1,318 @@
+//! Calculation of the time a frame occupies the medium.
+//!
+//! Durations follow the TXTIME equations of IEEE 802.11 for each PHY and are
+//! rounded up to whole microseconds. The frame length is the length of the
+//! MPDU including the FCS, so 4 bytes should be added for captures without
+//! the FCS.
+
+use crate::{
+    channel::Band,
+    field::{
+        ext::{GuardInterval, HTFormat, FEC},
+        rate::{self, DataRate},
+    },
+    phy::Phy,
+    Radiotap,
+};
+
+const SERVICE_BITS: u64 = 16;
+const TAIL_BITS: u64 = 6;
+
+/// The length of an ACK frame including the FCS.
+const ACK_LEN: usize = 14;
+
+/// The duration of a PPDU.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Ppdu {
+    /// The duration of the preamble and PHY headers in microseconds.
+    pub preamble_us: u32,
+    /// The duration of the whole PPDU in microseconds, including the
+    /// preamble.
+    pub duration_us: u32,
+}
+
+/// Returns the PPDU of a DSSS or HR/DSSS frame with a rate in units of 500
+/// kbps.
+pub fn dsss(len: usize, rate: u32, short_preamble: bool) -> Ppdu {
+    // The short preamble is not allowed at 1 Mbps
+    let preamble_us = if short_preamble && rate > 2 { 96 } else { 192 };
+    let data_us = div_ceil(len as u64 * 8 * 2, u64::from(rate.max(1)));
+    Ppdu {
+        preamble_us,
+        duration_us: preamble_us + data_us as u32,
+    }
+}
+
+/// Returns the PPDU of an OFDM frame with a rate in units of 500 kbps. ERP
+/// frames in the 2.4 GHz band have a 6 microsecond signal extension.
+pub fn ofdm(len: usize, rate: u32, signal_extension: bool) -> Ppdu {
+    // Each 4 microsecond symbol carries 2 bits per 500 kbps
+    let bits_per_symbol = u64::from(rate.max(1)) * 2;
+    let symbols = div_ceil(SERVICE_BITS + 8 * len as u64 + TAIL_BITS, bits_per_symbol);
+    let preamble_us = 20;
+    Ppdu {
+        preamble_us,
+        duration_us: preamble_us + 4 * symbols as u32 + extension(signal_extension),
+    }
+}
+
+/// Returns the PPDU of an HT frame.
+pub fn ht(
+    len: usize,
+    rate: &DataRate,
+    format: HTFormat,
+    nsts: u8,
+    fec: FEC,
+    signal_extension: bool,
+) -> Ppdu {
+    let ltf = match nsts {
+        0 | 1 => 1,
+        2 => 2,
+        _ => 4,
+    };
+    let preamble_us = match format {
+        HTFormat::Mixed => 32 + 4 * ltf,
+        HTFormat::Greenfield => 24 + 4 * (ltf - 1),
+    };
+
+    // Rates above 300 Mbps use two BCC encoders
+    let encoders = if rate.kbps > 300_000 { 2 } else { 1 };
+    let data_us = data_us(len, rate, nsts > rate.nss, fec, encoders);
+
+    Ppdu {
+        preamble_us,
+        duration_us: preamble_us + data_us + extension(signal_extension),
+    }
+}
+
+/// Returns the PPDU of a single user VHT frame.
+pub fn vht(len: usize, rate: &DataRate, nsts: u8, fec: FEC) -> Ppdu {
+    let ltf = match nsts {
+        0 | 1 => 1,
+        2 => 2,
+        3 | 4 => 4,
+        5 | 6 => 6,
+        _ => 8,
+    };
+    let preamble_us = 36 + 4 * ltf;
+
+    // Each BCC encoder handles up to 600 Mbps
+    let encoders = div_ceil(rate.kbps, 600_000).max(1);
+    let data_us = data_us(len, rate, nsts > rate.nss, fec, encoders);
+
+    Ppdu {
+        preamble_us,
+        duration_us: preamble_us + data_us,
+    }
+}
+
+/// Returns the PPDU of a single user HE frame, assuming 2x HE-LTF symbols, LDPC
+/// coding, and no packet extension.
+pub fn he(len: usize, rate: &DataRate, nsts: u8) -> Ppdu {
+    let ltf = match nsts {
+        0 | 1 => 1,
+        2 => 2,
+        3 | 4 => 4,
+        5 | 6 => 6,
+        _ => 8,
+    };
+    // A 2x HE-LTF symbol is 6.4 microseconds plus the guard interval
+    let gi_ns = rate.symbol_ns - 12_800;
+    let ltf_ns = ltf * (6_400 + gi_ns);
+    let preamble_us = 36 + div_ceil(u64::from(ltf_ns), 1000) as u32;
+
+    let data_us = data_us(len, rate, nsts > rate.nss, FEC::LDPC, 1);
+
+    Ppdu {
+        preamble_us,
+        duration_us: preamble_us + data_us,
+    }
+}
+
+/// Returns the duration of the data symbols, rounded up to a multiple of 4
+/// microseconds.
+fn data_us(len: usize, rate: &DataRate, stbc: bool, fec: FEC, encoders: u64) -> u32 {
+    let tail = match fec {
+        FEC::BCC => TAIL_BITS * encoders,
+        FEC::LDPC => 0,
+    };
+    let bits = SERVICE_BITS + 8 * len as u64 + tail;
+
+    // STBC needs an even number of symbols
+    let m_stbc = if stbc { 2 } else { 1 };
+    let symbols = m_stbc * div_ceil(bits, m_stbc * rate.bits_per_symbol().max(1));
+
+    4 * div_ceil(symbols * u64::from(rate.symbol_ns), 4000) as u32
+}
+
+/// Divides rounding up, as `u64::div_ceil` needs Rust 1.73.
+#[allow(clippy::manual_div_ceil)]
+fn div_ceil(a: u64, b: u64) -> u64 {
+    (a + b - 1) / b
+}
+
+fn extension(signal_extension: bool) -> u32 {
+    if signal_extension {
+        6
+    } else {
+        0
+    }
+}
+
+impl Radiotap {
+    /// Returns the PPDU that carried a frame of the given length.
+    ///
+    /// HE, EHT, and S1G frames are not supported since their fields are not
+    /// parsed, see [airtime::he](airtime/fn.he.html) for HE frames.
+    pub fn ppdu(&self, len: usize) -> Option<Ppdu> {
+        let ghz2 = self.ghz2();
+
+        match self.phy()? {
+            Phy::Dsss | Phy::HrDsss => {
+                let short_preamble = matches!(self.flags, Some(flags) if flags.preamble);
+                Some(dsss(len, self.rate_units()?, short_preamble))
+            }
+            Phy::ErpOfdm | Phy::Ofdm => Some(ofdm(len, self.rate_units()?, ghz2)),
+            Phy::Ht => {
+                let mcs = self.mcs?;
+                let rate = rate::ht(mcs.index?, mcs.bw?, mcs.gi?).ok()?;
+                let nsts = rate.nss + mcs.stbc.unwrap_or(0);
+                Some(ht(
+                    len,
+                    &rate,
+                    mcs.format.unwrap_or(HTFormat::Mixed),
+                    nsts,
+                    mcs.fec.unwrap_or(FEC::BCC),
+                    ghz2,
+                ))
+            }
+            Phy::Vht => {
+                let vht = self.vht?;
+                let user = vht.users.iter().flatten().next()?;
+                let gi = vht.gi.unwrap_or(GuardInterval::Long);
+                let rate = rate::vht(user.index, vht.bw?, gi, user.nss).ok()?;
+                Some(self::vht(len, &rate, user.nsts, user.fec))
+            }
+            Phy::He | Phy::Eht | Phy::S1g => None,
+        }
+    }
+
+    /// Returns the airtime of a frame of the given length in microseconds.
+    pub fn airtime(&self, len: usize) -> Option<u32> {
+        Some(self.ppdu(len)?.duration_us)
+    }
+
+    /// Returns the airtime of a frame of the given length in microseconds,
+    /// including the SIFS and the ACK sent in response.
+    ///
+    /// The ACK is assumed to be sent at the highest mandatory rate not above
+    /// the rate of the frame, and at 24 Mbps for HT and VHT frames.
+    pub fn airtime_with_ack(&self, len: usize) -> Option<u32> {
+        let airtime = self.airtime(len)?;
+        let ghz2 = self.ghz2();
+        let sifs = if ghz2 { 10 } else { 16 };
+
+        let ack = match self.phy()? {
+            Phy::Dsss | Phy::HrDsss => {
+                let rate = [22, 11, 4, 2]
+                    .iter()
+                    .cloned()
+                    .find(|&r| r <= self.rate_units().unwrap_or(2))
+                    .unwrap_or(2);
+                let short_preamble = matches!(self.flags, Some(flags) if flags.preamble);
+                dsss(ACK_LEN, rate, short_preamble)
+            }
+            Phy::ErpOfdm | Phy::Ofdm => {
+                let rate = [48, 24, 12]
+                    .iter()
+                    .cloned()
+                    .find(|&r| r <= self.rate_units().unwrap_or(12))
+                    .unwrap_or(12);
+                ofdm(ACK_LEN, rate, ghz2)
+            }
+            _ => ofdm(ACK_LEN, 48, ghz2),
+        };
+
+        Some(airtime + sifs + ack.duration_us)
+    }
+
+    /// Returns the legacy rate in units of 500 kbps.
+    fn rate_units(&self) -> Option<u32> {
+        Some((self.rate?.value * 2.0).round() as u32)
+    }
+
+    fn ghz2(&self) -> bool {
+        match (self.xchannel, self.channel) {
+            (Some(x), _) => x.flags.ghz2 || x.band() == Some(Band::Ghz2),
+            (None, Some(c)) => c.flags.ghz2 || c.band() == Some(Band::Ghz2),
+            (None, None) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::{ext::*, *};
+
+    #[test]
+    fn legacy() {
+        // 1500 bytes at 11 Mbps with a long preamble
+        assert_eq!(dsss(1500, 22, false).duration_us, 192 + 1091);
+        assert_eq!(dsss(1500, 2, true).preamble_us, 192);
+
+        // 1500 bytes at 54 Mbps, 56 symbols
+        assert_eq!(ofdm(1500, 108, false).duration_us, 20 + 224);
+        assert_eq!(ofdm(14, 48, true).duration_us, 34);
+    }
+
+    #[test]
+    fn ht_mixed_and_greenfield() {
+        let bw = Bandwidth::new(0).unwrap();
+        let rate = rate::ht(7, bw, GuardInterval::Long).unwrap();
+
+        // 1500 bytes at 65 Mbps is 47 symbols
+        let ppdu = ht(1500, &rate, HTFormat::Mixed, 1, FEC::BCC, false);
+        assert_eq!(ppdu.preamble_us, 36);
+        assert_eq!(ppdu.duration_us, 36 + 188);
+
+        let ppdu = ht(1500, &rate, HTFormat::Greenfield, 2, FEC::BCC, false);
+        assert_eq!(ppdu.preamble_us, 28);
+        assert_eq!(ppdu.duration_us, 28 + 192);
+    }
+
+    #[test]
+    fn radiotap_vht() {
+        let radiotap = Radiotap {
+            vht: Some(VHT {
+                bw: Some(Bandwidth::new(4).unwrap()),
+                gi: Some(GuardInterval::Short),
+                users: [
+                    Some(VHTUser {
+                        index: 9,
+                        fec: FEC::LDPC,
+                        nss: 2,
+                        nsts: 2,
+                        datarate: None,
+                    }),
+                    None,
+                    None,
+                    None,
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // 3120 bits per symbol, 8016 bits is 3 symbols of 3.6 microseconds
+        let ppdu = radiotap.ppdu(1000).unwrap();
+        assert_eq!(ppdu.preamble_us, 44);
+        assert_eq!(ppdu.duration_us, 44 + 12);
+        assert_eq!(radiotap.airtime_with_ack(1000), Some(56 + 16 + 28));
+    }
+
+    #[test]
+    fn unknown_phy() {
+        assert_eq!(Radiotap::default().airtime(100), None);
+    }
+}
@@ -0,0 +1,232 @@
+//! Reassembly of A-MPDUs from the [AMPDUStatus](../field/struct.AMPDUStatus.html)
+//! field of consecutive captured frames.
+//!
+//! ```
+//! use radiotap::ampdu::Aggregator;
+//!
+//! # let captures: Vec<Vec<u8>> = Vec::new();
+//! let mut aggregator = Aggregator::new();
+//!
+//! for capture in &captures {
+//!     for summary in aggregator.push(capture).unwrap() {
+//!         println!("{:?}", summary);
+//!     }
+//! }
+//!
+//! if let Some(summary) = aggregator.finish() {
+//!     println!("{:?}", summary);
+//! }
+//! ```
+
+use crate::{field::Kind, Radiotap, RadiotapIterator, Result};
+
+/// A summary of the subframes of a single A-MPDU.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AMPDUSummary {
+    /// The A-MPDU reference number.
+    pub reference: u32,
+    /// The number of subframes, including 0-length subframes.
+    pub subframes: usize,
+    /// The number of 0-length subframes.
+    pub zero_length_subframes: usize,
+    /// The total length of the captured subframes in bytes.
+    pub bytes: usize,
+    /// The number of subframes whose delimiter CRC check failed.
+    pub delimiter_crc_errors: usize,
+    /// Whether the driver reports the last subframe flag but no subframe had
+    /// it set, indicating that the end of the A-MPDU was not captured.
+    pub missing_last: bool,
+    /// The Radiotap header of the first subframe, which carries the PHY
+    /// information of the whole A-MPDU.
+    pub first: Radiotap,
+}
+
+/// Groups consecutive frames into A-MPDUs by their reference number.
+///
+/// An A-MPDU ends when a subframe has the last flag set, when a frame with a
+/// different reference number is pushed, or when a frame that was not part of
+/// an A-MPDU is pushed.
+#[derive(Clone, Debug, Default)]
+pub struct Aggregator {
+    current: Option<AMPDUSummary>,
+    last_known: bool,
+}
+
+impl Aggregator {
+    pub fn new() -> Aggregator {
+        Aggregator::default()
+    }
+
+    /// Adds a captured frame, with the 802.11 frame following its Radiotap
+    /// header, and returns the summaries of the A-MPDUs it completed. A single
+    /// frame can complete two A-MPDUs, the previous one by changing the
+    /// reference number and its own by having the last flag set.
+    pub fn push(&mut self, capture: &[u8]) -> Result<Vec<AMPDUSummary>> {
+        let (radiotap, frame) = Radiotap::parse(capture)?;
+        let mut completed = Vec::new();
+        match radiotap.ampdu_status {
+            Some(status) => {
+                if matches!(&self.current, Some(c) if c.reference != status.reference) {
+                    completed.extend(self.complete(false));
+                }
+
+                let current = self.current.get_or_insert_with(|| AMPDUSummary {
+                    reference: status.reference,
+                    subframes: 0,
+                    zero_length_subframes: 0,
+                    bytes: 0,
+                    delimiter_crc_errors: 0,
+                    missing_last: false,
+                    first: radiotap.clone(),
+                });
+
+                current.subframes += 1;
+                if status.zero_length == Some(true) {
+                    current.zero_length_subframes += 1;
+                } else {
+                    current.bytes += frame.len();
+                }
+                if delimiter_crc_error(capture)? {
+                    current.delimiter_crc_errors += 1;
+                }
+
+                self.last_known |= status.last.is_some();
+                if status.last == Some(true) {
+                    completed.extend(self.complete(true));
+                }
+            }
+            None => completed.extend(self.complete(false)),
+        }
+
+        Ok(completed)
+    }
+
+    /// Returns the summary of the A-MPDU in progress, call this after the last
+    /// frame was pushed.
+    pub fn finish(&mut self) -> Option<AMPDUSummary> {
+        self.complete(false)
+    }
+
+    fn complete(&mut self, last_seen: bool) -> Option<AMPDUSummary> {
+        let mut summary = self.current.take()?;
+        summary.missing_last = self.last_known && !last_seen;
+        self.last_known = false;
+        Some(summary)
+    }
+}
+
+/// Returns whether the A-MPDU status field of a capture has the delimiter CRC
+/// error flag set. The parsed field only records that the CRC is not known.
+fn delimiter_crc_error(capture: &[u8]) -> Result<bool> {
+    for element in RadiotapIterator::from_bytes(capture)? {
+        if let (Kind::AMPDUStatus, data) = element? {
+            return Ok(matches!(data.get(4), Some(flags) if flags & 0x10 != 0));
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::AMPDUStatus;
+
+    /// Returns a capture of a subframe with a payload of `len` bytes.
+    fn subframe(reference: u32, last: Option<bool>, crc_error: bool, len: usize) -> Vec<u8> {
+        let radiotap = Radiotap {
+            ampdu_status: Some(AMPDUStatus {
+                reference,
+                last,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut capture = radiotap.to_bytes();
+        if crc_error {
+            // The flags follow the reference number after the 8 byte header
+            capture[12] |= 0x10;
+        }
+        capture.resize(capture.len() + len, 0);
+        capture
+    }
+
+    #[test]
+    fn complete_with_last() {
+        let mut aggregator = Aggregator::new();
+
+        assert!(aggregator
+            .push(&subframe(1, Some(false), false, 100))
+            .unwrap()
+            .is_empty());
+        assert!(aggregator
+            .push(&subframe(1, Some(false), true, 50))
+            .unwrap()
+            .is_empty());
+        let mut completed = aggregator
+            .push(&subframe(1, Some(true), false, 10))
+            .unwrap();
+        assert_eq!(completed.len(), 1);
+        let summary = completed.remove(0);
+
+        assert_eq!(summary.reference, 1);
+        assert_eq!(summary.subframes, 3);
+        assert_eq!(summary.bytes, 160);
+        assert_eq!(summary.delimiter_crc_errors, 1);
+        assert!(!summary.missing_last);
+        assert!(aggregator.finish().is_none());
+    }
+
+    #[test]
+    fn missing_last() {
+        let mut aggregator = Aggregator::new();
+
+        assert!(aggregator
+            .push(&subframe(1, Some(false), false, 4))
+            .unwrap()
+            .is_empty());
+        let first = aggregator
+            .push(&subframe(2, Some(false), false, 4))
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].reference, 1);
+        assert!(first[0].missing_last);
+
+        let second = aggregator.finish().unwrap();
+        assert_eq!(second.reference, 2);
+        assert!(second.missing_last);
+        assert!(aggregator.finish().is_none());
+    }
+
+    #[test]
+    fn reference_change_and_last() {
+        let mut aggregator = Aggregator::new();
+
+        aggregator
+            .push(&subframe(1, Some(false), false, 4))
+            .unwrap();
+        let completed = aggregator.push(&subframe(2, Some(true), false, 8)).unwrap();
+
+        assert_eq!(completed.len(), 2);
+        assert_eq!(completed[0].reference, 1);
+        assert!(completed[0].missing_last);
+        assert_eq!(completed[1].reference, 2);
+        assert_eq!(completed[1].bytes, 8);
+        assert!(!completed[1].missing_last);
+        assert!(aggregator.finish().is_none());
+    }
+
+    #[test]
+    fn last_not_reported() {
+        let mut aggregator = Aggregator::new();
+
+        aggregator.push(&subframe(7, None, false, 4)).unwrap();
+        aggregator.push(&subframe(7, None, false, 4)).unwrap();
+        let summary = aggregator
+            .push(&Radiotap::default().to_bytes())
+            .unwrap()
+            .remove(0);
+
+        assert_eq!(summary.subframes, 2);
+        assert!(!summary.missing_last);
+    }
+}
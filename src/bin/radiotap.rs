@@ -0,0 +1,378 @@
+//! Inspects the Radiotap headers of the packets in a pcap or pcapng capture.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::File,
+    io::{BufRead, BufReader},
+    process,
+};
+
+use radiotap::{
+    capture::{pcap, pcapng, Packet},
+    channel::{freq_to_channel, Band},
+    field::Header,
+    filter::Filter,
+    schema,
+    stats::{chains, Distribution, SignalStats, Stats},
+    Error, Radiotap,
+};
+
+const USAGE: &str = "\
+Inspect the Radiotap headers of the packets in a pcap or pcapng capture.
+
+USAGE:
+    radiotap <COMMAND> [--filter <EXPRESSION>] <FILE>
+
+COMMANDS:
+    dump        Print the fields of each Radiotap header
+    fields      Count the packets each field appears in
+    stats       Summarize the signal, noise, and rates
+    validate    Check the headers against the Radiotap specification
+
+OPTIONS:
+    --filter <EXPRESSION>    Only inspect packets matching the expression, for
+                             example `channel.freq == 5180 && antenna_signal > -60`";
+
+type Result<T> = std::result::Result<T, Error>;
+
+type Packets = Box<dyn Iterator<Item = Result<Packet>>>;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (command, filter, path) = match args.as_slice() {
+        [command, path] => (command.as_str(), None, path.as_str()),
+        [command, flag, filter, path] if flag == "--filter" => {
+            (command.as_str(), Some(filter.as_str()), path.as_str())
+        }
+        [flag] if flag == "-h" || flag == "--help" => {
+            println!("{}", USAGE);
+            return;
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let mut packets = match open(path) {
+        Ok(packets) => packets,
+        Err(e) => {
+            eprintln!("error: failed to open `{}`: {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    if let Some(filter) = filter {
+        let filter = match Filter::new(filter) {
+            Ok(filter) => filter,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(2);
+            }
+        };
+        // Errors are passed through so they are still reported
+        packets = Box::new(packets.filter(move |packet| match packet {
+            Ok(packet) => filter.matches(&packet.radiotap),
+            Err(_) => true,
+        }));
+    }
+
+    let result = match command {
+        "dump" => dump(packets),
+        "fields" => fields(packets),
+        "stats" => stats(packets),
+        "validate" => validate(packets),
+        _ => {
+            eprintln!("error: unknown command `{}`\n\n{}", command, USAGE);
+            process::exit(2);
+        }
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Opens a pcap or pcapng capture, detected by the magic number.
+fn open(path: &str) -> Result<Packets> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let pcapng = reader.fill_buf()?.starts_with(&[0x0a, 0x0d, 0x0d, 0x0a]);
+    Ok(if pcapng {
+        Box::new(pcapng::Reader::new(reader)?)
+    } else {
+        Box::new(pcap::Reader::new(reader)?)
+    })
+}
+
+/// Returns the packets that were read, with their index in the capture, and
+/// reports the errors of the others. The readers stop by themselves after an
+/// error that leaves them in the middle of a record, so reading can always
+/// continue after an error.
+fn read(packets: Packets, errors: &mut u64) -> impl Iterator<Item = (usize, Packet)> + '_ {
+    packets
+        .enumerate()
+        .filter_map(move |(i, packet)| match packet {
+            Ok(packet) => Some((i, packet)),
+            Err(e) => {
+                eprintln!("error: packet #{}: {}", i, e);
+                *errors += 1;
+                None
+            }
+        })
+}
+
+fn dump(packets: Packets) -> Result<bool> {
+    let mut errors = 0;
+    for (i, packet) in read(packets, &mut errors) {
+        match packet.timestamp {
+            Some(t) => println!(
+                "#{} {}.{:09} len {}",
+                i,
+                t.as_secs(),
+                t.subsec_nanos(),
+                packet.original_len
+            ),
+            None => println!("#{} len {}", i, packet.original_len),
+        }
+
+        print_fields(&packet.radiotap);
+        for bit in unsupported(&packet) {
+            println!("    unsupported field {}", bit);
+        }
+    }
+    Ok(errors == 0)
+}
+
+/// Prints the members of the present fields, a line per field.
+fn print_fields(radiotap: &Radiotap) {
+    let mut fields: Vec<(&str, Vec<String>)> = Vec::new();
+    for member in schema::members() {
+        let value = match member.get(radiotap) {
+            Some(value) => value,
+            None => continue,
+        };
+        let (field, text) = match member.path().split_once('.') {
+            Some((field, rest)) => (field, format!("{}={}", rest, value)),
+            None => (member.path(), value.to_string()),
+        };
+        match fields.last_mut() {
+            Some((last, values)) if *last == field => values.push(text),
+            _ => fields.push((field, vec![text])),
+        }
+    }
+
+    for (field, values) in fields {
+        println!("    {} {}", field, values.join(" "));
+    }
+}
+
+/// Returns the bit numbers of the unsupported fields of a packet that was
+/// already parsed.
+fn unsupported(packet: &Packet) -> Vec<u8> {
+    Header::unsupported(&packet.data).unwrap_or_default()
+}
+
+fn fields(packets: Packets) -> Result<bool> {
+    let mut total = 0;
+    let mut errors = 0;
+    // Keyed by bit number, vendor namespaces last
+    let mut counts: BTreeMap<u8, (String, u64)> = BTreeMap::new();
+
+    for (_, packet) in read(packets, &mut errors) {
+        let unsupported = unsupported(&packet);
+        let header = packet.radiotap.header;
+        total += 1;
+
+        let mut seen: Vec<(u8, String)> = header
+            .present
+            .iter()
+            .map(|kind| match kind.value() {
+                Some(bit) => (bit, format!("{:?}", kind)),
+                None => (u8::MAX, "VendorNamespace".to_string()),
+            })
+            .chain(
+                unsupported
+                    .into_iter()
+                    .map(|bit| (bit, format!("Unsupported({})", bit))),
+            )
+            .collect();
+        seen.sort();
+        seen.dedup();
+
+        for (bit, name) in seen {
+            counts.entry(bit).or_insert((name, 0)).1 += 1;
+        }
+    }
+
+    println!("{} packets", total);
+    for (name, count) in counts.values() {
+        let percent = 100.0 * *count as f64 / total as f64;
+        println!("{:>20} {:>10} {:>6.1}%", name, count, percent);
+    }
+    Ok(errors == 0)
+}
+
+fn stats(packets: Packets) -> Result<bool> {
+    let mut stats = Stats::new();
+    let mut errors = 0;
+    let mut invalid = 0;
+    for (i, packet) in read(packets, &mut errors) {
+        if let Err(e) = stats.add_capture(&packet.data) {
+            eprintln!("error: packet #{}: {}", i, e);
+            invalid += 1;
+        }
+    }
+
+    println!("all frames");
+    print_stats(&stats.total);
+
+    for (antenna, antenna_stats) in &stats.antennas {
+        println!("\nantenna {}", antenna);
+        print_stats(antenna_stats);
+    }
+
+    let mut transmitters: Vec<_> = stats.transmitters.iter().collect();
+    transmitters.sort_by_key(|(address, s)| (std::cmp::Reverse(s.frames), **address));
+    for (address, transmitter_stats) in transmitters {
+        let address: Vec<_> = address.iter().map(|b| format!("{:02x}", b)).collect();
+        println!("\ntransmitter {}", address.join(":"));
+        print_stats(transmitter_stats);
+    }
+    Ok(errors + invalid == 0)
+}
+
+fn print_stats(stats: &SignalStats) {
+    fn print_distribution(name: &str, unit: &str, d: &Distribution) {
+        if let (Some(min), Some(mean), Some(max)) = (d.min(), d.mean(), d.max()) {
+            println!(
+                "    {:<8} min {} mean {:.1} p10 {} p50 {} p90 {} max {} {}",
+                name,
+                min,
+                mean,
+                d.percentile(10.0).unwrap(),
+                d.percentile(50.0).unwrap(),
+                d.percentile(90.0).unwrap(),
+                max,
+                unit
+            );
+        }
+    }
+
+    println!("    frames   {}", stats.frames);
+    print_distribution("signal", "dBm", &stats.signal);
+    print_distribution("noise", "dBm", &stats.noise);
+    print_distribution("snr", "dB", &stats.snr);
+    for (rate, count) in &stats.rates {
+        println!("    {:<8} {:?} {}", "rate", rate, count);
+    }
+}
+
+fn validate(packets: Packets) -> Result<bool> {
+    let mut total = 0;
+    let mut problems = 0;
+
+    for (i, packet) in packets.enumerate() {
+        total += 1;
+        let packet = match packet {
+            Ok(packet) => packet,
+            Err(e) => {
+                println!("#{}: {}", i, e);
+                problems += 1;
+                continue;
+            }
+        };
+
+        for problem in lint(&packet) {
+            println!("#{}: {}", i, problem);
+            problems += 1;
+        }
+        if let Err(e) = chains(&packet.data) {
+            println!("#{}: {}", i, e);
+            problems += 1;
+        }
+
+        // Fields of the specification that this crate does not parse
+        for bit in unsupported(&packet) {
+            if bit < 29 {
+                println!("#{}: note: field {} is not checked", i, bit);
+            }
+        }
+    }
+
+    println!("{} packets, {} problems", total, problems);
+    Ok(problems == 0)
+}
+
+/// Returns the problems with a Radiotap header that parsed successfully.
+fn lint(packet: &Packet) -> Vec<String> {
+    let radiotap = &packet.radiotap;
+    let mut problems = Vec::new();
+
+    let rates = [
+        radiotap.rate.is_some(),
+        radiotap.mcs.is_some(),
+        radiotap.vht.is_some(),
+    ];
+    if rates.iter().filter(|&&r| r).count() > 1 {
+        problems.push("more than one of the Rate, MCS, and VHT fields".to_string());
+    }
+
+    if let Some(channel) = radiotap.channel {
+        if channel.flags.ghz2 && channel.flags.ghz5 {
+            problems.push("channel flags are both 2 GHz and 5 GHz".to_string());
+        }
+        let band = channel.band();
+        if (channel.flags.ghz2 && band != Some(Band::Ghz2))
+            || (channel.flags.ghz5 && !matches!(band, Some(Band::Ghz5) | Some(Band::Ghz6)))
+        {
+            problems.push(format!(
+                "channel frequency {} MHz does not match the band flags",
+                channel.freq
+            ));
+        }
+        if freq_to_channel(u32::from(channel.freq)).is_none() {
+            problems.push(format!(
+                "channel frequency {} MHz is not a channel center",
+                channel.freq
+            ));
+        }
+    }
+
+    if let (Some(channel), Some(xchannel)) = (radiotap.channel, radiotap.xchannel) {
+        if channel.freq != xchannel.freq {
+            problems.push(format!(
+                "channel frequency {} MHz differs from the XChannel frequency {} MHz",
+                channel.freq, xchannel.freq
+            ));
+        }
+    }
+
+    if let Some(index) = radiotap.mcs.and_then(|mcs| mcs.index) {
+        if index > 76 {
+            problems.push(format!("MCS index {} is out of range", index));
+        }
+    }
+
+    if let Some(vht) = radiotap.vht {
+        for user in vht.users.iter().flatten() {
+            if user.index > 9 || user.nss > 8 {
+                problems.push(format!(
+                    "VHT user with MCS {} and {} spatial streams is out of range",
+                    user.index, user.nss
+                ));
+            }
+        }
+    }
+
+    if matches!(radiotap.flags, Some(flags) if flags.fcs) && packet.payload().len() < 4 {
+        problems.push("FCS flag set but the frame is shorter than the FCS".to_string());
+    }
+
+    problems
+}
@@ -0,0 +1,289 @@
+//! Merging of captures taken by several sniffers on the same channel into a
+//! single timeline.
+//!
+//! The TSF timers of the sniffers are aligned using the beacons they have in
+//! common. A beacon is identified by its transmitter, sequence number, and
+//! the timestamp in its body, and the offset between two sniffers is the
+//! median difference of the TSFT they recorded for the shared beacons. Frames
+//! with identical MPDUs seen by several sniffers within a window of time are
+//! merged, keeping the signal each sniffer received them at.
+
+use std::collections::HashMap;
+
+use crate::{
+    capture::Packet,
+    ieee80211::{mgmt::ManagementFrame, Frame, MacAddress},
+};
+
+/// How a sniffer received a merged frame.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Observation {
+    /// The index of the capture of the sniffer.
+    pub sniffer: usize,
+    /// The index of the packet in the capture.
+    pub index: usize,
+    /// The TSFT recorded by the sniffer in microseconds.
+    pub tsft: Option<u64>,
+    /// The antenna signal in dBm.
+    pub antenna_signal: Option<i8>,
+}
+
+/// A frame of the merged timeline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergedFrame {
+    /// The time of the frame in microseconds of the TSF timer of the first
+    /// capture, or `None` if the sniffer could not be aligned.
+    pub time: Option<i64>,
+    /// The packet as captured by the first sniffer that observed it.
+    pub packet: Packet,
+    /// The sniffers that observed the frame.
+    pub observations: Vec<Observation>,
+}
+
+/// Returns the offset in microseconds to add to the TSFT of each capture to
+/// align it with the first capture, or `None` for captures that share no
+/// beacons with an aligned capture.
+pub fn offsets(captures: &[Vec<Packet>]) -> Vec<Option<i64>> {
+    // The TSFT each sniffer recorded for each beacon
+    let mut beacons: HashMap<(MacAddress, u16, u64), Vec<(usize, i64)>> = HashMap::new();
+    for (sniffer, packets) in captures.iter().enumerate() {
+        for packet in packets {
+            if let (Some(key), Some(tsft)) = (beacon_key(packet), packet.radiotap.tsft) {
+                beacons
+                    .entry(key)
+                    .or_default()
+                    .push((sniffer, tsft.value as i64));
+            }
+        }
+    }
+
+    let mut offsets = vec![None; captures.len()];
+    if let Some(first) = offsets.first_mut() {
+        *first = Some(0);
+    }
+
+    // Align captures to any aligned capture until no more can be aligned, so
+    // sniffers out of range of the first can be aligned through another
+    loop {
+        let mut progress = false;
+        for sniffer in 0..captures.len() {
+            if offsets[sniffer].is_some() {
+                continue;
+            }
+
+            let mut differences: HashMap<usize, Vec<i64>> = HashMap::new();
+            for observed in beacons.values() {
+                let tsft = match observed.iter().find(|(s, _)| *s == sniffer) {
+                    Some(&(_, tsft)) => tsft,
+                    None => continue,
+                };
+                for &(other, other_tsft) in observed {
+                    if let Some(offset) = offsets[other] {
+                        differences
+                            .entry(other)
+                            .or_default()
+                            .push(other_tsft + offset - tsft);
+                    }
+                }
+            }
+
+            // Use the aligned capture with the most shared beacons
+            if let Some((_, mut differences)) = differences
+                .into_iter()
+                .max_by_key(|(other, d)| (d.len(), std::cmp::Reverse(*other)))
+            {
+                differences.sort_unstable();
+                offsets[sniffer] = Some(differences[differences.len() / 2]);
+                progress = true;
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+
+    offsets
+}
+
+/// Merges the captures into a single timeline ordered by the aligned TSFT,
+/// followed by the frames that could not be aligned.
+///
+/// Frames with the same MPDU, excluding the FCS, seen by different sniffers
+/// no more than `window` microseconds apart are merged into one.
+pub fn merge(captures: &[Vec<Packet>], window: u64) -> Vec<MergedFrame> {
+    let offsets = offsets(captures);
+
+    let mut aligned = Vec::new();
+    let mut unaligned = Vec::new();
+    for (sniffer, packets) in captures.iter().enumerate() {
+        for (index, packet) in packets.iter().enumerate() {
+            let time = match (offsets[sniffer], packet.radiotap.tsft) {
+                (Some(offset), Some(tsft)) => tsft.value as i64 + offset,
+                _ => {
+                    unaligned.push((sniffer, index, packet));
+                    continue;
+                }
+            };
+            aligned.push((time, sniffer, index, packet));
+        }
+    }
+    aligned.sort_by_key(|&(time, sniffer, index, _)| (time, sniffer, index));
+
+    let mut merged: Vec<MergedFrame> = Vec::new();
+    let mut last_seen: HashMap<&[u8], usize> = HashMap::new();
+    for (time, sniffer, index, packet) in aligned {
+        let observation = observation(sniffer, index, packet);
+        let mpdu = mpdu(packet);
+
+        if let Some(&i) = last_seen.get(mpdu) {
+            let frame = &mut merged[i];
+            let near = matches!(frame.time, Some(t) if (time - t).unsigned_abs() <= window);
+            if near && frame.observations.iter().all(|o| o.sniffer != sniffer) {
+                frame.observations.push(observation);
+                continue;
+            }
+        }
+
+        last_seen.insert(mpdu, merged.len());
+        merged.push(MergedFrame {
+            time: Some(time),
+            packet: packet.clone(),
+            observations: vec![observation],
+        });
+    }
+
+    merged.extend(
+        unaligned
+            .into_iter()
+            .map(|(sniffer, index, packet)| MergedFrame {
+                time: None,
+                packet: packet.clone(),
+                observations: vec![observation(sniffer, index, packet)],
+            }),
+    );
+    merged
+}
+
+fn observation(sniffer: usize, index: usize, packet: &Packet) -> Observation {
+    Observation {
+        sniffer,
+        index,
+        tsft: packet.radiotap.tsft.map(|tsft| tsft.value),
+        antenna_signal: packet.radiotap.antenna_signal.map(|signal| signal.value),
+    }
+}
+
+/// Returns the MPDU of the packet without the FCS.
+fn mpdu(packet: &Packet) -> &[u8] {
+    let payload = packet.payload();
+    match packet.radiotap.flags {
+        Some(flags) if flags.fcs && payload.len() >= 4 => &payload[..payload.len() - 4],
+        _ => payload,
+    }
+}
+
+/// Returns the transmitter, sequence number, and timestamp of a beacon.
+fn beacon_key(packet: &Packet) -> Option<(MacAddress, u16, u64)> {
+    let frame = Frame::parse(&packet.radiotap, packet.payload()).ok()?;
+    match ManagementFrame::parse(&frame).ok()? {
+        ManagementFrame::Beacon(beacon) => Some((
+            frame.header.addr2?,
+            frame.header.sequence_control?.sequence,
+            beacon.timestamp,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        field::{AntennaSignal, TSFT},
+        Radiotap,
+    };
+
+    fn packet(tsft: u64, signal: i8, frame: &[u8]) -> Packet {
+        let radiotap = Radiotap {
+            tsft: Some(TSFT { value: tsft }),
+            antenna_signal: Some(AntennaSignal { value: signal }),
+            ..Default::default()
+        };
+        let mut data = radiotap.to_bytes();
+        data.extend_from_slice(frame);
+        Packet {
+            interface_id: 0,
+            timestamp: None,
+            original_len: data.len() as u32,
+            radiotap: Radiotap::from_bytes(&data).unwrap(),
+            data,
+        }
+    }
+
+    fn beacon(sequence: u8, timestamp: u8) -> Vec<u8> {
+        let mut frame = vec![0x80, 0, 0, 0];
+        frame.extend_from_slice(&[255; 6]);
+        frame.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        frame.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        frame.extend_from_slice(&[sequence << 4, 0]);
+        frame.extend_from_slice(&[timestamp, 0, 0, 0, 0, 0, 0, 0, 100, 0, 1, 0]);
+        frame
+    }
+
+    #[test]
+    fn align_and_deduplicate() {
+        let ack = [0xd4, 0, 0, 0, 9, 9, 9, 9, 9, 9];
+        let captures = vec![
+            vec![
+                packet(1_000, -40, &beacon(1, 1)),
+                packet(1_500, -41, &ack),
+                packet(103_400, -42, &beacon(2, 2)),
+            ],
+            // The second sniffer is 50 ms behind and misses the first beacon
+            vec![
+                packet(51_501, -70, &ack),
+                packet(153_400, -71, &beacon(2, 2)),
+                packet(153_600, -72, &ack),
+            ],
+            // The third sniffer shares no beacons with the others
+            vec![packet(10, -90, &beacon(3, 3))],
+        ];
+
+        assert_eq!(offsets(&captures), vec![Some(0), Some(-50_000), None]);
+
+        let merged = merge(&captures, 10);
+        let times: Vec<_> = merged.iter().map(|m| m.time).collect();
+        assert_eq!(
+            times,
+            vec![Some(1_000), Some(1_500), Some(103_400), Some(103_600), None]
+        );
+
+        let signals: Vec<_> = merged[1]
+            .observations
+            .iter()
+            .map(|o| (o.sniffer, o.antenna_signal))
+            .collect();
+        assert_eq!(signals, vec![(0, Some(-41)), (1, Some(-70))]);
+        assert_eq!(merged[2].observations.len(), 2);
+        assert_eq!(merged[3].observations.len(), 1);
+        assert_eq!(merged[3].packet.payload(), &ack);
+    }
+
+    #[test]
+    fn align_through_another_capture() {
+        let captures = vec![
+            vec![packet(1_000, -40, &beacon(1, 1))],
+            vec![
+                packet(2_000, -50, &beacon(1, 1)),
+                packet(5_000, -50, &beacon(2, 2)),
+            ],
+            vec![packet(8_000, -60, &beacon(2, 2))],
+        ];
+
+        assert_eq!(
+            offsets(&captures),
+            vec![Some(0), Some(-1_000), Some(-4_000)]
+        );
+        assert!(merge(&captures, 10).iter().all(|m| m.time.is_some()));
+    }
+}
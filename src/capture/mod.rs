@@ -0,0 +1,79 @@
+//! Readers and writers for packet capture files containing Radiotap frames.
+
+#[cfg(feature = "ieee80211")]
+pub mod merge;
+pub mod pcap;
+pub mod pcapng;
+
+use byteorder::{ByteOrder, BE, LE};
+use std::{
+    io::{self, Read},
+    time::Duration,
+};
+
+use crate::{Error, Radiotap, Result};
+
+/// The link type for 802.11 frames preceded by a Radiotap header.
+pub const LINKTYPE_IEEE802_11_RADIOTAP: u16 = 127;
+
+/// A captured packet from an interface with a Radiotap link type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    /// The index of the interface the packet was captured on.
+    pub interface_id: u32,
+    /// The time the packet was captured, relative to the Unix epoch. Not all
+    /// capture blocks record a timestamp.
+    pub timestamp: Option<Duration>,
+    /// The length of the packet as it was on the wire.
+    pub original_len: u32,
+    /// The parsed Radiotap header.
+    pub radiotap: Radiotap,
+    /// The captured bytes, including the Radiotap header.
+    pub data: Vec<u8>,
+}
+
+impl Packet {
+    /// Returns the captured bytes following the Radiotap header.
+    pub fn payload(&self) -> &[u8] {
+        &self.data[self.radiotap.header.length..]
+    }
+}
+
+/// The byte order of a capture file or section.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    pub(crate) fn u16(self, buf: &[u8]) -> u16 {
+        match self {
+            Endian::Little => LE::read_u16(buf),
+            Endian::Big => BE::read_u16(buf),
+        }
+    }
+
+    pub(crate) fn u32(self, buf: &[u8]) -> u32 {
+        match self {
+            Endian::Little => LE::read_u32(buf),
+            Endian::Big => BE::read_u32(buf),
+        }
+    }
+}
+
+/// Fills `buf` from the reader, returning `false` if the reader was already at
+/// the end of its input.
+pub(crate) fn read_exact_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => return Err(Error::IncompleteError),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
@@ -0,0 +1,265 @@
+//! A reader and writer for the classic [pcap](https://wiki.wireshark.org/Development/LibpcapFileFormat)
+//! capture format.
+
+use std::{
+    convert::TryFrom,
+    io::{Read, Write},
+    time::Duration,
+};
+
+use crate::{
+    capture::{read_exact_or_eof, Endian, Packet, LINKTYPE_IEEE802_11_RADIOTAP},
+    Error, Radiotap, Result,
+};
+
+const MAGIC_MICROSECONDS: u32 = 0xA1B2_C3D4;
+const MAGIC_NANOSECONDS: u32 = 0xA1B2_3C4D;
+
+const SNAP_LEN: u32 = 65535;
+
+/// The largest record accepted by the reader, the maximum snapshot length of
+/// libpcap.
+const MAX_CAPTURED_LEN: u32 = 262_144;
+
+/// Reads Radiotap packets from a pcap capture.
+#[derive(Debug)]
+pub struct Reader<R> {
+    reader: R,
+    endian: Endian,
+    nanoseconds: bool,
+    failed: bool,
+}
+
+impl<R: Read> Reader<R> {
+    /// Creates a new reader, reading the global header. The capture must have
+    /// the Radiotap link type.
+    pub fn new(mut reader: R) -> Result<Reader<R>> {
+        let mut header = [0; 24];
+        reader.read_exact(&mut header)?;
+
+        let (endian, nanoseconds) = match Endian::Little.u32(&header) {
+            MAGIC_MICROSECONDS => (Endian::Little, false),
+            MAGIC_NANOSECONDS => (Endian::Little, true),
+            magic => match magic.swap_bytes() {
+                MAGIC_MICROSECONDS => (Endian::Big, false),
+                MAGIC_NANOSECONDS => (Endian::Big, true),
+                _ => return Err(Error::InvalidCaptureFile),
+            },
+        };
+
+        let link_type = endian.u32(&header[20..]);
+        if link_type != u32::from(LINKTYPE_IEEE802_11_RADIOTAP) {
+            return Err(Error::UnsupportedLinkType(link_type));
+        }
+
+        Ok(Reader {
+            reader,
+            endian,
+            nanoseconds,
+            failed: false,
+        })
+    }
+
+    /// Returns the next packet in the capture, or `None` if the end of the
+    /// capture has been reached.
+    pub fn next_packet(&mut self) -> Result<Option<Packet>> {
+        if self.failed {
+            return Ok(None);
+        }
+        let result = self.read_record();
+        if result.is_err() {
+            self.failed = true;
+        }
+        let (timestamp, original_len, data) = match result? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Packet {
+            interface_id: 0,
+            timestamp: Some(timestamp),
+            original_len,
+            radiotap: Radiotap::from_bytes(&data)?,
+            data,
+        }))
+    }
+
+    /// Reads the next record header and the captured bytes.
+    fn read_record(&mut self) -> Result<Option<(Duration, u32, Vec<u8>)>> {
+        let mut header = [0; 16];
+        if !read_exact_or_eof(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+
+        let secs = u64::from(self.endian.u32(&header[0..]));
+        let fraction = self.endian.u32(&header[4..]);
+        let captured_len = self.endian.u32(&header[8..]);
+        let original_len = self.endian.u32(&header[12..]);
+
+        let nanos = if self.nanoseconds {
+            fraction
+        } else {
+            fraction.saturating_mul(1000)
+        };
+        if nanos >= 1_000_000_000 {
+            return Err(Error::InvalidCaptureFile);
+        }
+
+        if captured_len > MAX_CAPTURED_LEN {
+            return Err(Error::InvalidCaptureFile);
+        }
+
+        let mut data = vec![0; captured_len as usize];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some((Duration::new(secs, nanos), original_len, data)))
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_packet().transpose()
+    }
+}
+
+/// Writes Radiotap packets to a pcap capture with microsecond timestamps.
+#[derive(Debug)]
+pub struct Writer<W> {
+    writer: W,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new writer, writing the global header.
+    pub fn new(mut writer: W) -> Result<Writer<W>> {
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&MAGIC_MICROSECONDS.to_le_bytes());
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        header.extend_from_slice(&0i32.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&SNAP_LEN.to_le_bytes());
+        header.extend_from_slice(&u32::from(LINKTYPE_IEEE802_11_RADIOTAP).to_le_bytes());
+        writer.write_all(&header)?;
+        Ok(Writer { writer })
+    }
+
+    /// Writes a packet consisting of the encoded Radiotap header followed by
+    /// the 802.11 frame.
+    pub fn write_packet(
+        &mut self,
+        timestamp: Duration,
+        radiotap: &Radiotap,
+        frame: &[u8],
+    ) -> Result<()> {
+        let mut data = radiotap.to_bytes();
+        data.extend_from_slice(frame);
+        self.write_raw(timestamp, &data)
+    }
+
+    /// Writes a packet that already starts with a Radiotap header. Packets
+    /// longer than the snapshot length are truncated, keeping their original
+    /// length in the record.
+    pub fn write_raw(&mut self, timestamp: Duration, data: &[u8]) -> Result<()> {
+        let secs = u32::try_from(timestamp.as_secs())
+            .map_err(|_| Error::UnwritablePacket("timestamp does not fit in a pcap record"))?;
+        let original_len = u32::try_from(data.len())
+            .map_err(|_| Error::UnwritablePacket("packet does not fit in a pcap record"))?;
+        let captured_len = original_len.min(SNAP_LEN);
+
+        let mut header = Vec::with_capacity(16);
+        header.extend_from_slice(&secs.to_le_bytes());
+        header.extend_from_slice(&timestamp.subsec_micros().to_le_bytes());
+        header.extend_from_slice(&captured_len.to_le_bytes());
+        header.extend_from_slice(&original_len.to_le_bytes());
+        self.writer.write_all(&header)?;
+        self.writer.write_all(&data[..captured_len as usize])?;
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::{Rate, TSFT};
+
+    #[test]
+    fn round_trip() {
+        let radiotap = Radiotap {
+            tsft: Some(TSFT { value: 1234 }),
+            rate: Some(Rate { value: 54.0 }),
+            ..Default::default()
+        };
+        let frame = [0xd4, 0x00, 0x00, 0x00, 1, 2, 3, 4, 5, 6];
+
+        let mut writer = Writer::new(Vec::new()).unwrap();
+        writer
+            .write_packet(Duration::new(10, 250_000), &radiotap, &frame)
+            .unwrap();
+        let capture = writer.into_inner().unwrap();
+
+        let packets: Vec<Packet> = Reader::new(&capture[..])
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].timestamp, Some(Duration::new(10, 250_000)));
+        assert_eq!(packets[0].radiotap.tsft, radiotap.tsft);
+        assert_eq!(packets[0].radiotap.rate, radiotap.rate);
+        assert_eq!(packets[0].payload(), &frame);
+    }
+
+    #[test]
+    fn bad_link_type() {
+        let mut capture = Writer::new(Vec::new()).unwrap().into_inner().unwrap();
+        capture[20] = 105;
+
+        match Reader::new(&capture[..]).unwrap_err() {
+            Error::UnsupportedLinkType(105) => {}
+            e => panic!("Error not UnsupportedLinkType: {:?}", e),
+        };
+    }
+
+    #[test]
+    fn truncated_records() {
+        let mut data = Radiotap::default().to_bytes();
+        data.resize(70_000, 0xff);
+
+        let mut writer = Writer::new(Vec::new()).unwrap();
+        writer.write_raw(Duration::from_secs(1), &data).unwrap();
+        match writer
+            .write_raw(Duration::from_secs(u64::from(u32::MAX) + 1), &data)
+            .unwrap_err()
+        {
+            Error::UnwritablePacket(_) => {}
+            e => panic!("Error not UnwritablePacket: {:?}", e),
+        };
+        let mut capture = writer.into_inner().unwrap();
+        assert_eq!(capture.len(), 24 + 16 + SNAP_LEN as usize);
+
+        let packet = Reader::new(&capture[..]).unwrap().next().unwrap().unwrap();
+        assert_eq!(packet.data.len(), SNAP_LEN as usize);
+        assert_eq!(packet.original_len, 70_000);
+
+        // A captured length beyond any snapshot length is rejected before
+        // reading the data
+        capture[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+        match Reader::new(&capture[..])
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err()
+        {
+            Error::InvalidCaptureFile => {}
+            e => panic!("Error not InvalidCaptureFile: {:?}", e),
+        };
+    }
+}
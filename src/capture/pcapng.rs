@@ -0,0 +1,535 @@
+//! A reader and writer for the [pcapng](https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html)
+//! capture format.
+//!
+//! Only packets captured on interfaces with the Radiotap link type are
+//! returned by the reader, all other packets are skipped.
+
+use byteorder::{ByteOrder, BE, LE};
+use std::{
+    convert::TryFrom,
+    io::{Read, Write},
+    time::Duration,
+};
+
+use crate::{
+    capture::{read_exact_or_eof, Endian, Packet, LINKTYPE_IEEE802_11_RADIOTAP},
+    Error, Radiotap, Result,
+};
+
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const SIMPLE_PACKET_BLOCK: u32 = 0x0000_0003;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+/// The largest block accepted by the reader, enough for any packet up to the
+/// maximum snapshot length of libpcap along with its options.
+const MAX_BLOCK_LEN: usize = 16 * 1024 * 1024;
+
+const OPT_ENDOFOPT: u16 = 0;
+const OPT_IF_TSRESOL: u16 = 9;
+
+/// The resolution of the timestamps recorded for an interface.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Resolution {
+    /// Timestamps are in units of 10^-n seconds.
+    Decimal(u8),
+    /// Timestamps are in units of 2^-n seconds.
+    Binary(u8),
+}
+
+impl Default for Resolution {
+    fn default() -> Resolution {
+        Resolution::Decimal(6)
+    }
+}
+
+impl Resolution {
+    /// Returns the resolution described by an `if_tsresol` option value.
+    pub fn new(value: u8) -> Resolution {
+        if value & 0x80 == 0 {
+            Resolution::Decimal(value)
+        } else {
+            Resolution::Binary(value & 0x7f)
+        }
+    }
+
+    /// Converts a timestamp in this resolution to a duration since the Unix
+    /// epoch.
+    pub fn to_duration(self, timestamp: u64) -> Result<Duration> {
+        let units = match self {
+            Resolution::Decimal(n) => 10u128.checked_pow(u32::from(n)),
+            Resolution::Binary(n) => 1u128.checked_shl(u32::from(n)),
+        }
+        .ok_or(Error::InvalidCaptureFile)?;
+
+        let timestamp = u128::from(timestamp);
+        let secs = (timestamp / units) as u64;
+        let nanos = (timestamp % units * 1_000_000_000 / units) as u32;
+        Ok(Duration::new(secs, nanos))
+    }
+}
+
+/// An interface described by an Interface Description Block.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Interface {
+    /// The link type of the interface.
+    pub link_type: u16,
+    /// The maximum number of bytes captured from each packet.
+    pub snap_len: u32,
+    /// The resolution of the packet timestamps.
+    pub resolution: Resolution,
+}
+
+/// Reads Radiotap packets from a pcapng capture.
+#[derive(Debug)]
+pub struct Reader<R> {
+    reader: R,
+    endian: Endian,
+    interfaces: Vec<Interface>,
+    failed: bool,
+}
+
+impl<R: Read> Reader<R> {
+    /// Creates a new reader, reading the initial Section Header Block.
+    pub fn new(reader: R) -> Result<Reader<R>> {
+        let mut reader = Reader {
+            reader,
+            endian: Endian::Little,
+            interfaces: Vec::new(),
+            failed: false,
+        };
+        match reader.read_block()? {
+            Some((SECTION_HEADER_BLOCK, _)) => Ok(reader),
+            _ => Err(Error::InvalidCaptureFile),
+        }
+    }
+
+    /// Returns the interfaces described in the current section.
+    pub fn interfaces(&self) -> &[Interface] {
+        &self.interfaces
+    }
+
+    /// Returns the next Radiotap packet in the capture, or `None` if the end
+    /// of the capture has been reached.
+    pub fn next_packet(&mut self) -> Result<Option<Packet>> {
+        loop {
+            let (block_type, body) = match self.read_block()? {
+                Some(block) => block,
+                None => return Ok(None),
+            };
+
+            let (interface_id, timestamp, original_len, data) = match block_type {
+                SECTION_HEADER_BLOCK => {
+                    self.interfaces.clear();
+                    continue;
+                }
+                INTERFACE_DESCRIPTION_BLOCK => {
+                    let interface = self.parse_interface(&body)?;
+                    self.interfaces.push(interface);
+                    continue;
+                }
+                ENHANCED_PACKET_BLOCK => {
+                    if body.len() < 20 {
+                        return Err(Error::InvalidCaptureFile);
+                    }
+                    let interface_id = self.endian.u32(&body[0..]);
+                    let high = u64::from(self.endian.u32(&body[4..]));
+                    let low = u64::from(self.endian.u32(&body[8..]));
+                    let captured_len = self.endian.u32(&body[12..]) as usize;
+                    let original_len = self.endian.u32(&body[16..]);
+                    let data = body
+                        .get(20..20 + captured_len)
+                        .ok_or(Error::InvalidCaptureFile)?;
+                    (interface_id, Some(high << 32 | low), original_len, data)
+                }
+                SIMPLE_PACKET_BLOCK => {
+                    if body.len() < 4 {
+                        return Err(Error::InvalidCaptureFile);
+                    }
+                    let original_len = self.endian.u32(&body[0..]);
+                    let snap_len = self.interface(0)?.snap_len;
+                    let mut captured_len = (body.len() - 4).min(original_len as usize);
+                    if snap_len != 0 {
+                        captured_len = captured_len.min(snap_len as usize);
+                    }
+                    (0, None, original_len, &body[4..4 + captured_len])
+                }
+                _ => continue,
+            };
+
+            let interface = self.interface(interface_id)?;
+            if interface.link_type != LINKTYPE_IEEE802_11_RADIOTAP {
+                continue;
+            }
+
+            let timestamp = match timestamp {
+                Some(ts) => Some(interface.resolution.to_duration(ts)?),
+                None => None,
+            };
+
+            return Ok(Some(Packet {
+                interface_id,
+                timestamp,
+                original_len,
+                radiotap: Radiotap::from_bytes(data)?,
+                data: data.to_vec(),
+            }));
+        }
+    }
+
+    /// Returns the interface with the given id in the current section.
+    fn interface(&self, id: u32) -> Result<Interface> {
+        self.interfaces
+            .get(id as usize)
+            .copied()
+            .ok_or(Error::InvalidCaptureFile)
+    }
+
+    /// Parses the body of an Interface Description Block.
+    fn parse_interface(&self, body: &[u8]) -> Result<Interface> {
+        if body.len() < 8 {
+            return Err(Error::InvalidCaptureFile);
+        }
+
+        let mut interface = Interface {
+            link_type: self.endian.u16(&body[0..]),
+            snap_len: self.endian.u32(&body[4..]),
+            resolution: Resolution::default(),
+        };
+
+        let mut options = &body[8..];
+        while options.len() >= 4 {
+            let code = self.endian.u16(&options[0..]);
+            let length = self.endian.u16(&options[2..]) as usize;
+            let value = options
+                .get(4..4 + length)
+                .ok_or(Error::InvalidCaptureFile)?;
+
+            match code {
+                OPT_ENDOFOPT => break,
+                OPT_IF_TSRESOL if length == 1 => interface.resolution = Resolution::new(value[0]),
+                _ => {}
+            }
+
+            // Option values are padded to 32 bits
+            let padded = (4 + length + 3) & !3;
+            options = options.get(padded..).unwrap_or(&[]);
+        }
+
+        Ok(interface)
+    }
+
+    /// Reads the next block, returning its type and body. A Section Header
+    /// Block also updates the byte order used for the rest of the section.
+    fn read_block(&mut self) -> Result<Option<(u32, Vec<u8>)>> {
+        if self.failed {
+            return Ok(None);
+        }
+        let result = self.read_block_inner();
+        if result.is_err() {
+            // The block boundaries can no longer be trusted
+            self.failed = true;
+        }
+        result
+    }
+
+    fn read_block_inner(&mut self) -> Result<Option<(u32, Vec<u8>)>> {
+        let mut head = [0; 8];
+        if !read_exact_or_eof(&mut self.reader, &mut head)? {
+            return Ok(None);
+        }
+
+        let mut body = Vec::new();
+        let block_type = self.endian.u32(&head);
+
+        if block_type == SECTION_HEADER_BLOCK {
+            // The byte order magic is the first field of the body
+            let mut magic = [0; 4];
+            self.reader.read_exact(&mut magic)?;
+            self.endian = if LE::read_u32(&magic) == BYTE_ORDER_MAGIC {
+                Endian::Little
+            } else if BE::read_u32(&magic) == BYTE_ORDER_MAGIC {
+                Endian::Big
+            } else {
+                return Err(Error::InvalidCaptureFile);
+            };
+            body.extend_from_slice(&magic);
+        }
+
+        let length = self.endian.u32(&head[4..]) as usize;
+        if length < 12 + body.len() || length > MAX_BLOCK_LEN || length & 3 != 0 {
+            return Err(Error::InvalidCaptureFile);
+        }
+
+        let start = body.len();
+        body.resize(length - 12, 0);
+        self.reader.read_exact(&mut body[start..])?;
+
+        let mut trailer = [0; 4];
+        self.reader.read_exact(&mut trailer)?;
+        if self.endian.u32(&trailer) as usize != length {
+            return Err(Error::InvalidCaptureFile);
+        }
+
+        Ok(Some((block_type, body)))
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_packet().transpose()
+    }
+}
+
+/// Writes Radiotap packets to a pcapng capture with a single Radiotap
+/// interface and nanosecond timestamps.
+#[derive(Debug)]
+pub struct Writer<W> {
+    writer: W,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new writer, writing the Section Header Block and Interface
+    /// Description Block.
+    pub fn new(writer: W) -> Result<Writer<W>> {
+        let mut writer = Writer { writer };
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&(-1i64).to_le_bytes());
+        writer.write_block(SECTION_HEADER_BLOCK, &body)?;
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&LINKTYPE_IEEE802_11_RADIOTAP.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(&OPT_IF_TSRESOL.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&[9, 0, 0, 0]);
+        body.extend_from_slice(&OPT_ENDOFOPT.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        writer.write_block(INTERFACE_DESCRIPTION_BLOCK, &body)?;
+
+        Ok(writer)
+    }
+
+    /// Writes a packet consisting of the encoded Radiotap header followed by
+    /// the 802.11 frame.
+    pub fn write_packet(
+        &mut self,
+        timestamp: Duration,
+        radiotap: &Radiotap,
+        frame: &[u8],
+    ) -> Result<()> {
+        let mut data = radiotap.to_bytes();
+        data.extend_from_slice(frame);
+        self.write_raw(timestamp, &data)
+    }
+
+    /// Writes a packet that already starts with a Radiotap header.
+    pub fn write_raw(&mut self, timestamp: Duration, data: &[u8]) -> Result<()> {
+        let timestamp = u64::try_from(timestamp.as_nanos())
+            .map_err(|_| Error::UnwritablePacket("timestamp does not fit in a pcapng block"))?;
+        let len = u32::try_from(data.len())
+            .map_err(|_| Error::UnwritablePacket("packet does not fit in a pcapng block"))?;
+
+        let mut body = Vec::with_capacity(20 + data.len());
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(timestamp as u32).to_le_bytes());
+        body.extend_from_slice(&len.to_le_bytes());
+        body.extend_from_slice(&len.to_le_bytes());
+        body.extend_from_slice(data);
+        self.write_block(ENHANCED_PACKET_BLOCK, &body)
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Writes a block, padding the body to 32 bits.
+    fn write_block(&mut self, block_type: u32, body: &[u8]) -> Result<()> {
+        let padding = (4 - body.len() % 4) % 4;
+        let length = u32::try_from(12 + body.len() + padding)
+            .map_err(|_| Error::UnwritablePacket("block is too long for pcapng"))?;
+        self.writer.write_all(&block_type.to_le_bytes())?;
+        self.writer.write_all(&length.to_le_bytes())?;
+        self.writer.write_all(body)?;
+        self.writer.write_all(&[0; 3][..padding])?;
+        self.writer.write_all(&length.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIOTAP: [u8; 39] = [
+        0, 0, 39, 0, 46, 72, 0, 192, 0, 0, 0, 128, 0, 0, 0, 160, 4, 0, 0, 0, 16, 2, 158, 9, 160, 0,
+        227, 5, 0, 0, 255, 255, 255, 255, 2, 0, 222, 173, 4,
+    ];
+
+    fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let mut body = body.to_vec();
+        body.resize((body.len() + 3) & !3, 0);
+        let length = (body.len() + 12) as u32;
+        let mut block = Vec::new();
+        block.extend_from_slice(&block_type.to_le_bytes());
+        block.extend_from_slice(&length.to_le_bytes());
+        block.extend_from_slice(&body);
+        block.extend_from_slice(&length.to_le_bytes());
+        block
+    }
+
+    fn section_header() -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        body.extend_from_slice(&[1, 0, 0, 0]);
+        body.extend_from_slice(&(-1i64).to_le_bytes());
+        block(SECTION_HEADER_BLOCK, &body)
+    }
+
+    fn interface(link_type: u16, tsresol: Option<u8>) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&link_type.to_le_bytes());
+        body.extend_from_slice(&[0, 0]);
+        body.extend_from_slice(&0u32.to_le_bytes());
+        if let Some(tsresol) = tsresol {
+            body.extend_from_slice(&OPT_IF_TSRESOL.to_le_bytes());
+            body.extend_from_slice(&1u16.to_le_bytes());
+            body.extend_from_slice(&[tsresol, 0, 0, 0]);
+            body.extend_from_slice(&[0, 0, 0, 0]);
+        }
+        block(INTERFACE_DESCRIPTION_BLOCK, &body)
+    }
+
+    fn enhanced_packet(interface_id: u32, timestamp: u64, data: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&interface_id.to_le_bytes());
+        body.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(timestamp as u32).to_le_bytes());
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(data);
+        block(ENHANCED_PACKET_BLOCK, &body)
+    }
+
+    #[test]
+    fn radiotap_interfaces_only() {
+        let mut capture = section_header();
+        capture.extend(interface(1, None));
+        capture.extend(interface(127, Some(9)));
+        capture.extend(enhanced_packet(0, 1, &[0xff; 14]));
+        capture.extend(enhanced_packet(1, 1_500_000_000, &RADIOTAP));
+
+        let packets: Vec<Packet> = Reader::new(&capture[..])
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].interface_id, 1);
+        assert_eq!(packets[0].timestamp, Some(Duration::new(1, 500_000_000)));
+        assert_eq!(packets[0].radiotap.rate.unwrap().value, 2.0);
+        assert_eq!(packets[0].payload(), &[] as &[u8]);
+    }
+
+    #[test]
+    fn simple_packet() {
+        let mut body = Vec::new();
+        body.extend_from_slice(&(RADIOTAP.len() as u32).to_le_bytes());
+        body.extend_from_slice(&RADIOTAP);
+
+        let mut capture = section_header();
+        capture.extend(interface(127, None));
+        capture.extend(block(SIMPLE_PACKET_BLOCK, &body));
+
+        let packet = Reader::new(&capture[..])
+            .unwrap()
+            .next_packet()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(packet.timestamp, None);
+        assert_eq!(packet.data, &RADIOTAP[..]);
+    }
+
+    #[test]
+    fn binary_resolution() {
+        let ts = Resolution::new(0x80 | 10)
+            .to_duration(3 * 1024 + 256)
+            .unwrap();
+        assert_eq!(ts, Duration::new(3, 250_000_000));
+    }
+
+    #[test]
+    fn writer_round_trip() {
+        let radiotap = Radiotap::from_bytes(&RADIOTAP).unwrap();
+        let frame = [0x80, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+
+        let mut writer = Writer::new(Vec::new()).unwrap();
+        writer
+            .write_packet(Duration::new(7, 123), &radiotap, &frame)
+            .unwrap();
+        let capture = writer.into_inner().unwrap();
+
+        let packet = Reader::new(&capture[..])
+            .unwrap()
+            .next_packet()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(packet.timestamp, Some(Duration::new(7, 123)));
+        assert_eq!(packet.radiotap.rate, radiotap.rate);
+        assert_eq!(packet.radiotap.channel, radiotap.channel);
+        assert_eq!(packet.payload(), &frame);
+    }
+
+    #[test]
+    fn writer_timestamp_overflow() {
+        let mut writer = Writer::new(Vec::new()).unwrap();
+        match writer.write_raw(Duration::MAX, &RADIOTAP).unwrap_err() {
+            Error::UnwritablePacket(_) => {}
+            e => panic!("Error not UnwritablePacket: {:?}", e),
+        };
+    }
+
+    #[test]
+    fn oversized_block() {
+        let mut capture = section_header();
+        capture.extend_from_slice(&ENHANCED_PACKET_BLOCK.to_le_bytes());
+        capture.extend_from_slice(&0xffff_fffcu32.to_le_bytes());
+        capture.extend_from_slice(&[0; 8]);
+
+        // The length is rejected before allocating the body
+        match Reader::new(&capture[..])
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err()
+        {
+            Error::InvalidCaptureFile => {}
+            e => panic!("Error not InvalidCaptureFile: {:?}", e),
+        };
+    }
+
+    #[test]
+    fn bad_magic() {
+        let mut capture = section_header();
+        capture[8..12].copy_from_slice(&[0, 0, 0, 0]);
+
+        match Reader::new(&capture[..]).unwrap_err() {
+            Error::InvalidCaptureFile => {}
+            e => panic!("Error not InvalidCaptureFile: {:?}", e),
+        };
+    }
+}
@@ -0,0 +1,305 @@
+//! Conversion between channel center frequencies and channel numbers, and
+//! the spectrum occupied by a transmission.
+//!
+//! ```
+//! use radiotap::channel::{channel_to_freq, freq_to_channel, Band};
+//!
+//! assert_eq!(freq_to_channel(5955), Some(1));
+//! assert_eq!(Band::from_freq(5955), Some(Band::Ghz6));
+//! assert_eq!(channel_to_freq(36, Band::Ghz5), Some(5180));
+//! ```
+
+use crate::{
+    field::{
+        ext::{Bandwidth, XChannelFlags},
+        Channel, XChannel,
+    },
+    Radiotap,
+};
+
+/// A frequency band used by 802.11.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Band {
+    /// The sub-1 GHz (S1G) band used by 802.11ah.
+    Sub1Ghz,
+    /// The 2.4 GHz band.
+    Ghz2,
+    /// The 5 GHz band, including the 4.9 GHz public safety band.
+    Ghz5,
+    /// The 6 GHz band.
+    Ghz6,
+    /// The 60 GHz band used by 802.11ad/ay DMG.
+    Ghz60,
+}
+
+impl Band {
+    /// Returns the band containing the frequency in MHz.
+    pub fn from_freq(freq: u32) -> Option<Band> {
+        match freq {
+            1..=999 => Some(Band::Sub1Ghz),
+            2400..=2500 => Some(Band::Ghz2),
+            4900..=5924 => Some(Band::Ghz5),
+            5925..=7125 => Some(Band::Ghz6),
+            57000..=71000 => Some(Band::Ghz60),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the channel number for a center frequency in MHz.
+///
+/// Sub-1 GHz channels use the US channelization starting at 902 MHz, and are
+/// only matched on whole MHz center frequencies.
+pub fn freq_to_channel(freq: u32) -> Option<u8> {
+    let channel = match Band::from_freq(freq)? {
+        Band::Sub1Ghz if (902..=928).contains(&freq) => (freq - 902) * 2,
+        Band::Ghz2 if freq == 2484 => 14,
+        Band::Ghz2 if freq > 2407 => (freq - 2407) / 5,
+        Band::Ghz5 if (4910..=4980).contains(&freq) => (freq - 4000) / 5,
+        Band::Ghz5 if freq > 5000 => (freq - 5000) / 5,
+        Band::Ghz6 if freq == 5935 => 2,
+        Band::Ghz6 if freq > 5950 => (freq - 5950) / 5,
+        Band::Ghz60 if freq > 56160 => (freq - 56160) / 2160,
+        _ => return None,
+    };
+
+    // Make sure the channel number round trips, this rejects frequencies
+    // between channels or outside of the channelization of the band
+    if channel_to_freq(channel as u8, Band::from_freq(freq)?) == Some(freq) {
+        Some(channel as u8)
+    } else {
+        None
+    }
+}
+
+/// Returns the center frequency in MHz for a channel number in a band.
+///
+/// Sub-1 GHz channels with odd numbers are centered on a half MHz, their
+/// frequency is rounded down.
+pub fn channel_to_freq(channel: u8, band: Band) -> Option<u32> {
+    let channel = u32::from(channel);
+    match band {
+        Band::Sub1Ghz if (1..=52).contains(&channel) => Some(902 + channel / 2),
+        Band::Ghz2 if channel == 14 => Some(2484),
+        Band::Ghz2 if (1..=13).contains(&channel) => Some(2407 + 5 * channel),
+        Band::Ghz5 if (182..=196).contains(&channel) => Some(4000 + 5 * channel),
+        Band::Ghz5 if (1..=181).contains(&channel) => Some(5000 + 5 * channel),
+        Band::Ghz6 if channel == 2 => Some(5935),
+        Band::Ghz6 if (1..=233).contains(&channel) => Some(5950 + 5 * channel),
+        Band::Ghz60 if (1..=6).contains(&channel) => Some(56160 + 2160 * channel),
+        _ => None,
+    }
+}
+
+impl Channel {
+    /// Returns the band of the channel frequency.
+    pub fn band(&self) -> Option<Band> {
+        Band::from_freq(u32::from(self.freq))
+    }
+
+    /// Returns the channel number of the channel frequency.
+    pub fn number(&self) -> Option<u8> {
+        freq_to_channel(u32::from(self.freq))
+    }
+}
+
+impl XChannel {
+    /// Returns the band of the channel frequency.
+    pub fn band(&self) -> Option<Band> {
+        Band::from_freq(u32::from(self.freq))
+    }
+
+    /// Returns the channel number of the channel frequency, falling back to
+    /// the channel number reported by the driver.
+    pub fn number(&self) -> Option<u8> {
+        freq_to_channel(u32::from(self.freq)).or(match self.channel {
+            0 => None,
+            channel => Some(channel),
+        })
+    }
+}
+
+/// The spectrum occupied by a transmission.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Occupancy {
+    /// The center frequency of the occupied spectrum in MHz.
+    pub center_freq: u32,
+    /// The occupied bandwidth in MHz.
+    pub bandwidth: u32,
+    /// The center frequencies in MHz of the covered 20 MHz subchannels, lowest
+    /// first.
+    pub subchannels: Vec<u32>,
+}
+
+impl Bandwidth {
+    /// Returns the spectrum occupied by a transmission with this bandwidth,
+    /// given the frequency of the primary 20 MHz channel.
+    ///
+    /// In the 5 and 6 GHz bands the position of wider channels follows from
+    /// the fixed channelization. In the 2.4 GHz band a 40 MHz channel needs
+    /// the HT40+/- flags of the XChannel to locate the secondary channel.
+    pub fn occupancy(&self, primary_freq: u32, flags: Option<&XChannelFlags>) -> Option<Occupancy> {
+        let width = u32::from(self.bandwidth);
+        let primary_lower = primary_freq.checked_sub(10)?;
+
+        let lower = if width == 20 {
+            primary_lower
+        } else {
+            match Band::from_freq(primary_freq)? {
+                Band::Ghz2 => match (width, flags) {
+                    (40, Some(flags)) if flags.ht40u => primary_lower,
+                    (40, Some(flags)) if flags.ht40d => primary_lower.checked_sub(20)?,
+                    _ => return None,
+                },
+                band => {
+                    // The lower edge of the first channel in the channelization
+                    let base = match band {
+                        Band::Ghz5 if primary_freq >= 5745 => 5735,
+                        Band::Ghz5 => 5170,
+                        Band::Ghz6 => 5945,
+                        _ => return None,
+                    };
+                    let offset = i64::from(primary_lower) - base;
+                    (base + offset.div_euclid(i64::from(width)) * i64::from(width)) as u32
+                }
+            }
+        };
+
+        // With a sideband only part of the channel was used
+        let (lower, width) = match (self.sideband, self.sideband_index) {
+            (Some(sideband), Some(index)) => (
+                lower + u32::from(index) * u32::from(sideband),
+                u32::from(sideband),
+            ),
+            _ => (lower, width),
+        };
+
+        Some(Occupancy {
+            center_freq: lower + width / 2,
+            bandwidth: width,
+            subchannels: (0..width / 20).map(|i| lower + 10 + 20 * i).collect(),
+        })
+    }
+}
+
+impl Radiotap {
+    /// Returns the spectrum occupied by the frame, using the bandwidth of the
+    /// VHT or MCS field and the frequency of the XChannel or Channel field.
+    /// Frames without either bandwidth are assumed to use 20 MHz.
+    pub fn occupancy(&self) -> Option<Occupancy> {
+        let freq = match (self.xchannel, self.channel) {
+            (Some(xchannel), _) => xchannel.freq,
+            (None, Some(channel)) => channel.freq,
+            (None, None) => return None,
+        };
+
+        let bandwidth = match (self.vht.and_then(|v| v.bw), self.mcs.and_then(|m| m.bw)) {
+            (Some(bw), _) | (None, Some(bw)) => bw,
+            (None, None) => Bandwidth {
+                bandwidth: 20,
+                sideband: None,
+                sideband_index: None,
+            },
+        };
+
+        let flags = self.xchannel.map(|x| x.flags);
+        bandwidth.occupancy(u32::from(freq), flags.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let cases = [
+            (2412, 1, Band::Ghz2),
+            (2484, 14, Band::Ghz2),
+            (4940, 188, Band::Ghz5),
+            (5180, 36, Band::Ghz5),
+            (5825, 165, Band::Ghz5),
+            (5935, 2, Band::Ghz6),
+            (5955, 1, Band::Ghz6),
+            (7115, 233, Band::Ghz6),
+            (58320, 1, Band::Ghz60),
+            (69120, 6, Band::Ghz60),
+            (904, 4, Band::Sub1Ghz),
+        ];
+
+        for &(freq, channel, band) in cases.iter() {
+            assert_eq!(Band::from_freq(freq), Some(band), "{}", freq);
+            assert_eq!(freq_to_channel(freq), Some(channel), "{}", freq);
+            assert_eq!(channel_to_freq(channel, band), Some(freq), "{}", freq);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(freq_to_channel(2413), None);
+        assert_eq!(freq_to_channel(3000), None);
+        assert_eq!(freq_to_channel(5950), None);
+        assert_eq!(channel_to_freq(15, Band::Ghz2), None);
+        assert_eq!(channel_to_freq(7, Band::Ghz60), None);
+    }
+
+    #[test]
+    fn occupancy_5ghz() {
+        let bw = Bandwidth::new(11).unwrap();
+        let occupancy = bw.occupancy(5200, None).unwrap();
+        assert_eq!(occupancy.center_freq, 5250);
+        assert_eq!(occupancy.subchannels.len(), 8);
+        assert_eq!(occupancy.subchannels[0], 5180);
+
+        let bw = Bandwidth::new(4).unwrap();
+        let occupancy = bw.occupancy(5805, None).unwrap();
+        assert_eq!(occupancy.center_freq, 5775);
+        assert_eq!(occupancy.subchannels, vec![5745, 5765, 5785, 5805]);
+    }
+
+    #[test]
+    fn occupancy_sideband() {
+        // 20 MHz in the upper half of a 40 MHz channel
+        let bw = Bandwidth::new(3).unwrap();
+        let occupancy = bw.occupancy(5180, None).unwrap();
+        assert_eq!(occupancy.bandwidth, 20);
+        assert_eq!(occupancy.subchannels, vec![5200]);
+
+        let bw = Bandwidth::new(13).unwrap();
+        let occupancy = bw.occupancy(5975, None).unwrap();
+        assert_eq!(occupancy.center_freq, 6065);
+        assert_eq!(occupancy.bandwidth, 80);
+    }
+
+    #[test]
+    fn occupancy_ht40() {
+        let mut flags = XChannelFlags {
+            turbo: false,
+            cck: false,
+            ofdm: true,
+            ghz2: true,
+            ghz5: false,
+            passive: false,
+            dynamic: false,
+            gfsk: false,
+            gsm: false,
+            sturbo: false,
+            half: false,
+            quarter: false,
+            ht20: false,
+            ht40u: false,
+            ht40d: true,
+        };
+        let bw = Bandwidth::new(1).unwrap();
+
+        assert_eq!(bw.occupancy(2437, None), None);
+        assert_eq!(bw.occupancy(2437, Some(&flags)).unwrap().center_freq, 2427);
+
+        flags.ht40d = false;
+        flags.ht40u = true;
+        assert_eq!(
+            bw.occupancy(2437, Some(&flags)).unwrap().subchannels,
+            vec![2437, 2457]
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! Columnar export of parsed Radiotap headers to Apache Arrow and Parquet.
+//!
+//! The columns follow the [schema](../schema/index.html). Every field is a
+//! nullable column named after the field on [`Radiotap`](../struct.Radiotap.html).
+//! Fields with a single `value` member, like `tsft` and `antenna_signal`, are
+//! primitive columns and all other fields are struct columns with a nullable
+//! child for each member. The VHT users are a fixed size list, and enum
+//! members, like the guard interval, are strings of the variant name.
+//!
+//! ```
+//! use radiotap::{columnar::RecordBatchBuilder, field::TSFT, Radiotap};
+//!
+//! let mut builder = RecordBatchBuilder::new();
+//! builder.append(&Radiotap {
+//!     tsft: Some(TSFT { value: 1234 }),
+//!     ..Default::default()
+//! });
+//! builder.append(&Radiotap::default());
+//!
+//! let batch = builder.finish().unwrap();
+//! assert_eq!(batch.num_rows(), 2);
+//! assert_eq!(batch.column_by_name("tsft").unwrap().null_count(), 1);
+//! ```
+
+use std::{fmt, io::Write, sync::Arc, vec};
+
+use arrow::{
+    array::{
+        Array, ArrayRef, BooleanBufferBuilder, BooleanBuilder, FixedSizeListArray, Float32Builder,
+        Int8Builder, RecordBatch, StringBuilder, StructArray, UInt16Builder, UInt32Builder,
+        UInt64Builder, UInt8Builder,
+    },
+    buffer::NullBuffer,
+    compute::interleave,
+    datatypes::{Field, Schema, SchemaRef},
+};
+use parquet::arrow::ArrowWriter;
+
+use crate::{
+    schema::{self, Member, Presence, Type, Value},
+    Error, Radiotap, Result,
+};
+
+impl From<arrow::error::ArrowError> for Error {
+    fn from(e: arrow::error::ArrowError) -> Error {
+        Error::ColumnarError(Box::new(e))
+    }
+}
+
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(e: parquet::errors::ParquetError) -> Error {
+        Error::ColumnarError(Box::new(e))
+    }
+}
+
+/// The number of rows the Parquet writer buffers before writing a batch.
+const BATCH_SIZE: usize = 8192;
+
+/// A builder for the column of a member.
+enum Builder {
+    U8(UInt8Builder),
+    U16(UInt16Builder),
+    U32(UInt32Builder),
+    U64(UInt64Builder),
+    I8(Int8Builder),
+    F32(Float32Builder),
+    Bool(BooleanBuilder),
+    Str(StringBuilder),
+}
+
+impl Builder {
+    fn new(ty: Type) -> Builder {
+        match ty {
+            Type::U8 => Builder::U8(UInt8Builder::new()),
+            Type::U16 => Builder::U16(UInt16Builder::new()),
+            Type::U32 => Builder::U32(UInt32Builder::new()),
+            Type::U64 => Builder::U64(UInt64Builder::new()),
+            Type::I8 => Builder::I8(Int8Builder::new()),
+            Type::F32 => Builder::F32(Float32Builder::new()),
+            Type::Bool => Builder::Bool(BooleanBuilder::new()),
+            Type::Str => Builder::Str(StringBuilder::new()),
+        }
+    }
+
+    fn append(&mut self, value: Option<Value>) {
+        let uint = match value {
+            Some(Value::UInt(v)) => Some(v),
+            _ => None,
+        };
+        match self {
+            Builder::U8(b) => b.append_option(uint.map(|v| v as u8)),
+            Builder::U16(b) => b.append_option(uint.map(|v| v as u16)),
+            Builder::U32(b) => b.append_option(uint.map(|v| v as u32)),
+            Builder::U64(b) => b.append_option(uint),
+            Builder::I8(b) => b.append_option(match value {
+                Some(Value::Int(v)) => Some(v as i8),
+                _ => None,
+            }),
+            Builder::F32(b) => b.append_option(match value {
+                Some(Value::Float(v)) => Some(v),
+                _ => None,
+            }),
+            Builder::Bool(b) => b.append_option(match value {
+                Some(Value::Bool(v)) => Some(v),
+                _ => None,
+            }),
+            Builder::Str(b) => b.append_option(match value {
+                Some(Value::Str(v)) => Some(v),
+                _ => None,
+            }),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Builder::U8(b) => Arc::new(b.finish()),
+            Builder::U16(b) => Arc::new(b.finish()),
+            Builder::U32(b) => Arc::new(b.finish()),
+            Builder::U64(b) => Arc::new(b.finish()),
+            Builder::I8(b) => Arc::new(b.finish()),
+            Builder::F32(b) => Arc::new(b.finish()),
+            Builder::Bool(b) => Arc::new(b.finish()),
+            Builder::Str(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+fn field(name: &str, array: &ArrayRef) -> Field {
+    Field::new(name, array.data_type().clone(), true)
+}
+
+/// The finished member columns and struct validities of a batch, which are
+/// assembled into the nested columns.
+struct Parts {
+    leaves: vec::IntoIter<ArrayRef>,
+    validity: Vec<(&'static str, NullBuffer)>,
+    len: usize,
+}
+
+impl Parts {
+    fn validity(&self, path: &str) -> NullBuffer {
+        self.validity
+            .iter()
+            .find(|(p, _)| *p == path)
+            .map(|(_, validity)| validity.clone())
+            .expect("struct has a presence test")
+    }
+}
+
+/// Returns the columns of the members below the prefix, such as `channel.`.
+/// Members that share a struct are grouped into a struct column, and indexed
+/// members such as `vht.users[0]` into a fixed size list column.
+fn columns(
+    parts: &mut Parts,
+    prefix: &str,
+    members: &[&'static Member],
+) -> Result<Vec<(String, ArrayRef)>> {
+    let segment = |member: &Member| -> &'static str {
+        member.path()[prefix.len()..].split('.').next().unwrap()
+    };
+
+    let mut columns = Vec::new();
+    let mut i = 0;
+    while i < members.len() {
+        let name = segment(members[i]);
+        let len = members[i..]
+            .iter()
+            .take_while(|member| segment(member) == name)
+            .count();
+        let group = &members[i..i + len];
+        i += len;
+
+        if group[0].path().len() == prefix.len() + name.len() {
+            let leaf = parts.leaves.next().expect("column for every member");
+            columns.push((name.to_string(), leaf));
+            continue;
+        }
+
+        let path = format!("{}{}", prefix, name);
+        let children = self::columns(parts, &format!("{}.", path), group)?;
+        let fields: Vec<_> = children
+            .iter()
+            .map(|(name, array)| field(name, array))
+            .collect();
+        let arrays = children.into_iter().map(|(_, array)| array).collect();
+        let array: ArrayRef = Arc::new(StructArray::new(
+            fields.into(),
+            arrays,
+            Some(parts.validity(&path)),
+        ));
+        columns.push((name.to_string(), array));
+    }
+
+    lists(parts, prefix, columns)
+}
+
+/// Replaces the indexed columns, such as `users[0]` to `users[3]`, with a
+/// single fixed size list column.
+fn lists(
+    parts: &Parts,
+    prefix: &str,
+    columns: Vec<(String, ArrayRef)>,
+) -> Result<Vec<(String, ArrayRef)>> {
+    let mut result: Vec<(String, ArrayRef)> = Vec::new();
+    let mut elements: Vec<ArrayRef> = Vec::new();
+    let mut columns = columns.into_iter().peekable();
+
+    while let Some((name, array)) = columns.next() {
+        let base = match name.find('[') {
+            Some(end) => name[..end].to_string(),
+            None => {
+                result.push((name, array));
+                continue;
+            }
+        };
+        elements.push(array);
+        if matches!(columns.peek(), Some((next, _)) if next.starts_with(&format!("{}[", base))) {
+            continue;
+        }
+
+        let arrays: Vec<&dyn Array> = elements.iter().map(|array| array.as_ref()).collect();
+        let indices: Vec<_> = (0..parts.len)
+            .flat_map(|row| (0..arrays.len()).map(move |element| (element, row)))
+            .collect();
+        let values = interleave(&arrays, &indices)?;
+        // The list is present whenever the struct containing it is
+        let array = FixedSizeListArray::new(
+            Arc::new(field("item", &values)),
+            arrays.len() as i32,
+            values,
+            Some(parts.validity(prefix.trim_end_matches('.'))),
+        );
+        result.push((base, Arc::new(array)));
+        elements.clear();
+    }
+
+    Ok(result)
+}
+
+/// Returns the Arrow schema of the record batches.
+pub fn schema() -> SchemaRef {
+    RecordBatchBuilder::new()
+        .finish()
+        .expect("empty batch is valid")
+        .schema()
+}
+
+/// Builds Arrow record batches from Radiotap headers.
+///
+/// Each row is appended directly to a column builder for every member of the
+/// schema, and the struct and list columns are assembled when the batch is
+/// finished.
+pub struct RecordBatchBuilder {
+    leaves: Vec<(&'static Member, Builder)>,
+    structs: Vec<(&'static str, Presence, BooleanBufferBuilder)>,
+    len: usize,
+}
+
+impl Default for RecordBatchBuilder {
+    fn default() -> RecordBatchBuilder {
+        RecordBatchBuilder {
+            leaves: schema::members()
+                .iter()
+                .map(|member| (member, Builder::new(member.ty())))
+                .collect(),
+            structs: schema::structs()
+                .iter()
+                .map(|&(path, present)| (path, present, BooleanBufferBuilder::new(0)))
+                .collect(),
+            len: 0,
+        }
+    }
+}
+
+impl fmt::Debug for RecordBatchBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RecordBatchBuilder")
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl RecordBatchBuilder {
+    /// Creates a new, empty builder.
+    pub fn new() -> RecordBatchBuilder {
+        RecordBatchBuilder::default()
+    }
+
+    /// Appends a Radiotap header as a row.
+    pub fn append(&mut self, radiotap: &Radiotap) {
+        for (member, builder) in &mut self.leaves {
+            builder.append(member.get(radiotap));
+        }
+        for (_, present, validity) in &mut self.structs {
+            validity.append(present(radiotap));
+        }
+        self.len += 1;
+    }
+
+    /// Returns the number of rows appended since the last batch.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no rows were appended since the last batch.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a record batch of the appended rows and resets the builder.
+    pub fn finish(&mut self) -> Result<RecordBatch> {
+        let mut parts = Parts {
+            leaves: self
+                .leaves
+                .iter_mut()
+                .map(|(_, builder)| builder.finish())
+                .collect::<Vec<_>>()
+                .into_iter(),
+            validity: self
+                .structs
+                .iter_mut()
+                .map(|(path, _, validity)| (*path, NullBuffer::new(validity.finish())))
+                .collect(),
+            len: self.len,
+        };
+        self.len = 0;
+
+        let members: Vec<_> = schema::members().iter().collect();
+        let (fields, arrays): (Vec<_>, Vec<_>) = columns(&mut parts, "", &members)?
+            .into_iter()
+            .map(|(name, array)| (field(&name, &array), array))
+            .unzip();
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+    }
+}
+
+/// Writes Radiotap headers to a Parquet file.
+#[derive(Debug)]
+pub struct ParquetWriter<W: Write + Send> {
+    writer: ArrowWriter<W>,
+    builder: RecordBatchBuilder,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    /// Creates a new Parquet writer.
+    pub fn new(writer: W) -> Result<ParquetWriter<W>> {
+        Ok(ParquetWriter {
+            writer: ArrowWriter::try_new(writer, schema(), None)?,
+            builder: RecordBatchBuilder::new(),
+        })
+    }
+
+    /// Writes a Radiotap header, buffering it until a full batch is ready.
+    pub fn write(&mut self, radiotap: &Radiotap) -> Result<()> {
+        self.builder.append(radiotap);
+        if self.builder.len() >= BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered Radiotap headers as a batch.
+    pub fn flush(&mut self) -> Result<()> {
+        if !self.builder.is_empty() {
+            let batch = self.builder.finish()?;
+            self.writer.write(&batch)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered Radiotap headers and the Parquet footer, and
+    /// returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.writer.into_inner()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs::File, process};
+
+    use arrow::{
+        array::AsArray,
+        datatypes::{DataType, Int8Type, UInt64Type, UInt8Type},
+    };
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use super::*;
+    use crate::tests::vht_radiotap;
+
+    #[test]
+    fn record_batch() {
+        let mut builder = RecordBatchBuilder::new();
+        builder.append(&Radiotap::default());
+        builder.append(&vht_radiotap());
+        let batch = builder.finish().unwrap();
+        assert!(builder.is_empty());
+        assert_eq!(batch.schema(), schema());
+        assert_eq!(batch.num_rows(), 2);
+
+        let signal = batch.column_by_name("antenna_signal").unwrap();
+        assert_eq!(signal.data_type(), &DataType::Int8);
+        assert_eq!(
+            signal.as_primitive::<Int8Type>().iter().collect::<Vec<_>>(),
+            [None, Some(-43)]
+        );
+
+        let vht = batch.column_by_name("vht").unwrap().as_struct();
+        assert!(vht.is_null(0));
+        assert_eq!(
+            vht.column_by_name("gi")
+                .unwrap()
+                .as_string::<i32>()
+                .value(1),
+            "Long"
+        );
+
+        let users = vht.column_by_name("users").unwrap().as_fixed_size_list();
+        let user = users.value(1);
+        let user = user.as_struct();
+        assert!(user.is_valid(0));
+        assert!(user.is_null(1));
+        assert_eq!(
+            user.column_by_name("nss")
+                .unwrap()
+                .as_primitive::<UInt8Type>()
+                .value(0),
+            3
+        );
+        assert_eq!(
+            user.column_by_name("fec")
+                .unwrap()
+                .as_string::<i32>()
+                .value(0),
+            "BCC"
+        );
+
+        // The builder starts over after a batch
+        builder.append(&vht_radiotap());
+        let batch = builder.finish().unwrap();
+        assert_eq!(batch.num_rows(), 1);
+        assert_eq!(batch.column_by_name("vht").unwrap().null_count(), 0);
+    }
+
+    #[test]
+    fn schema_members() {
+        fn leaves(data_type: &DataType) -> usize {
+            match data_type {
+                DataType::Struct(fields) => fields.iter().map(|f| leaves(f.data_type())).sum(),
+                DataType::FixedSizeList(item, len) => *len as usize * leaves(item.data_type()),
+                _ => 1,
+            }
+        }
+
+        let schema = schema();
+        let count: usize = schema.fields().iter().map(|f| leaves(f.data_type())).sum();
+        assert_eq!(count, schema::members().len());
+
+        let timestamp = schema.field_with_name("timestamp").unwrap();
+        let mcs = schema.field_with_name("mcs").unwrap();
+        match (timestamp.data_type(), mcs.data_type()) {
+            (DataType::Struct(timestamp), DataType::Struct(mcs)) => {
+                assert!(timestamp.find("position").is_some());
+                assert!(mcs.find("format").is_some());
+            }
+            other => panic!("not structs: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parquet() {
+        let path = env::temp_dir().join(format!("radiotap-{}.parquet", process::id()));
+        let mut writer = ParquetWriter::new(File::create(&path).unwrap()).unwrap();
+        for _ in 0..BATCH_SIZE + 10 {
+            writer.write(&vht_radiotap()).unwrap();
+        }
+        writer.write(&Radiotap::default()).unwrap();
+        writer.into_inner().unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(batches[0].schema(), schema());
+        let tsft: Vec<_> = batches
+            .iter()
+            .flat_map(|batch| {
+                let tsft = batch
+                    .column_by_name("tsft")
+                    .unwrap()
+                    .as_primitive::<UInt64Type>();
+                tsft.iter().collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(tsft.len(), BATCH_SIZE + 11);
+        assert_eq!(tsft[0], Some(2_593_857_465));
+        assert_eq!(tsft[BATCH_SIZE + 10], None);
+    }
+}
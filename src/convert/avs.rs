@@ -0,0 +1,190 @@
+//! Conversion of AVS capture headers to Radiotap.
+//!
+//! The MAC time is converted to the TSFT field. The SSI type decides whether
+//! the signal and noise become the dBm or the dB antenna fields.
+
+use byteorder::{ReadBytesExt, BE};
+use std::io::Cursor;
+
+use crate::{
+    convert::{channel, finish, rewrite},
+    field::*,
+    Error, Radiotap, Result,
+};
+
+/// The link type for 802.11 frames preceded by an AVS header.
+pub const LINKTYPE_AVS: u32 = 163;
+
+const VERSION_MASK: u32 = 0xFFFF_FFF0;
+const VERSION: u32 = 0x8021_1000;
+
+const PHYTYPE_DSSS_DOT11_97: u32 = 2;
+const PHYTYPE_DSSS_DOT11_B: u32 = 4;
+const PHYTYPE_PBCC_DOT11_B: u32 = 5;
+const PHYTYPE_OFDM_DOT11_G: u32 = 6;
+const PHYTYPE_PBCC_DOT11_G: u32 = 7;
+const PHYTYPE_OFDM_DOT11_A: u32 = 8;
+
+const SSI_TYPE_DBM: u32 = 2;
+const SSI_TYPE_RAW_RSSI: u32 = 3;
+
+const PREAMBLE_SHORT: u32 = 1;
+
+/// Returns the [Radiotap](../../struct.Radiotap.html) equivalent of the AVS
+/// header at the start of the input.
+pub fn from_bytes(input: &[u8]) -> Result<Radiotap> {
+    Ok(parse(input)?.0)
+}
+
+/// Returns the [Radiotap](../../struct.Radiotap.html) equivalent of the AVS
+/// header at the start of the input, and the 802.11 frame following it.
+pub fn parse(input: &[u8]) -> Result<(Radiotap, &[u8])> {
+    let mut cursor = Cursor::new(input);
+
+    let version = cursor.read_u32::<BE>()?;
+    if version & VERSION_MASK != VERSION {
+        return Err(Error::UnsupportedVersion);
+    }
+    let length = cursor.read_u32::<BE>()? as usize;
+    let mactime = cursor.read_u64::<BE>()?;
+    let _hosttime = cursor.read_u64::<BE>()?;
+    let phytype = cursor.read_u32::<BE>()?;
+    let number = cursor.read_u32::<BE>()?;
+    let datarate = cursor.read_u32::<BE>()?;
+    let antenna = cursor.read_u32::<BE>()?;
+    let _priority = cursor.read_u32::<BE>()?;
+    let ssi_type = cursor.read_u32::<BE>()?;
+    let ssi_signal = cursor.read_i32::<BE>()?;
+    let ssi_noise = cursor.read_i32::<BE>()?;
+    let preamble = cursor.read_u32::<BE>()?;
+
+    if input.len() < length || (length as u64) < cursor.position() {
+        return Err(Error::InvalidLength);
+    }
+
+    let mut radiotap = Radiotap::default();
+
+    if mactime != 0 {
+        radiotap.tsft = Some(TSFT { value: mactime });
+    }
+
+    radiotap.flags = Some(Flags {
+        cfp: false,
+        preamble: preamble == PREAMBLE_SHORT,
+        wep: false,
+        fragmentation: false,
+        fcs: false,
+        data_pad: false,
+        bad_fcs: false,
+        sgi: false,
+    });
+
+    // The data rate is in units of 100 kbps
+    if datarate != 0 {
+        radiotap.rate = Some(Rate {
+            value: datarate as f32 / 10.0,
+        });
+    }
+
+    let cck = matches!(
+        phytype,
+        PHYTYPE_DSSS_DOT11_97 | PHYTYPE_DSSS_DOT11_B | PHYTYPE_PBCC_DOT11_B | PHYTYPE_PBCC_DOT11_G
+    );
+    let ofdm = matches!(phytype, PHYTYPE_OFDM_DOT11_G | PHYTYPE_OFDM_DOT11_A);
+    radiotap.channel = channel(number, phytype == PHYTYPE_OFDM_DOT11_A, cck, ofdm);
+
+    if antenna <= u32::from(u8::MAX) {
+        radiotap.antenna = Some(Antenna {
+            value: antenna as u8,
+        });
+    }
+
+    match ssi_type {
+        SSI_TYPE_DBM => {
+            radiotap.antenna_signal = Some(AntennaSignal {
+                value: clamp_i8(ssi_signal),
+            });
+            radiotap.antenna_noise = Some(AntennaNoise {
+                value: clamp_i8(ssi_noise),
+            });
+        }
+        SSI_TYPE_RAW_RSSI => {
+            radiotap.antenna_signal_db = Some(AntennaSignalDb {
+                value: clamp_u8(ssi_signal),
+            });
+            radiotap.antenna_noise_db = Some(AntennaNoiseDb {
+                value: clamp_u8(ssi_noise),
+            });
+        }
+        _ => {}
+    }
+
+    Ok((finish(radiotap)?, &input[length..]))
+}
+
+/// Returns the captured frame with the AVS header replaced by the equivalent
+/// Radiotap header.
+pub fn to_radiotap_frame(input: &[u8]) -> Result<Vec<u8>> {
+    let (radiotap, frame) = parse(input)?;
+    Ok(rewrite(&radiotap, frame))
+}
+
+fn clamp_i8(value: i32) -> i8 {
+    value.max(i32::from(i8::MIN)).min(i32::from(i8::MAX)) as i8
+}
+
+fn clamp_u8(value: i32) -> u8 {
+    value.max(0).min(i32::from(u8::MAX)) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn avs(phytype: u32, channel: u32, ssi_type: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&0x8021_1001u32.to_be_bytes());
+        out.extend_from_slice(&64u32.to_be_bytes());
+        out.extend_from_slice(&5000u64.to_be_bytes());
+        out.extend_from_slice(&9999u64.to_be_bytes());
+        out.extend_from_slice(&phytype.to_be_bytes());
+        out.extend_from_slice(&channel.to_be_bytes());
+        out.extend_from_slice(&540u32.to_be_bytes());
+        out.extend_from_slice(&1u32.to_be_bytes());
+        out.extend_from_slice(&0u32.to_be_bytes());
+        out.extend_from_slice(&ssi_type.to_be_bytes());
+        out.extend_from_slice(&(-55i32).to_be_bytes());
+        out.extend_from_slice(&(-92i32).to_be_bytes());
+        out.extend_from_slice(&2u32.to_be_bytes());
+        out.extend_from_slice(&3u32.to_be_bytes());
+        out.extend_from_slice(&[0x08, 0x00]);
+        out
+    }
+
+    #[test]
+    fn ofdm_dot11_a() {
+        let input = avs(PHYTYPE_OFDM_DOT11_A, 36, SSI_TYPE_DBM);
+        let (radiotap, frame) = parse(&input).unwrap();
+
+        assert_eq!(frame, &[0x08, 0x00]);
+        assert_eq!(radiotap.tsft, Some(TSFT { value: 5000 }));
+        assert_eq!(radiotap.rate, Some(Rate { value: 54.0 }));
+        assert_eq!(radiotap.channel.unwrap().freq, 5180);
+        assert!(radiotap.channel.unwrap().flags.ofdm);
+        assert_eq!(radiotap.antenna_signal, Some(AntennaSignal { value: -55 }));
+        assert_eq!(radiotap.antenna_noise, Some(AntennaNoise { value: -92 }));
+    }
+
+    #[test]
+    fn radiotap_frame() {
+        let input = avs(PHYTYPE_DSSS_DOT11_B, 6, SSI_TYPE_DBM);
+        let output = to_radiotap_frame(&input).unwrap();
+        let (radiotap, frame) = Radiotap::parse(&output).unwrap();
+
+        assert_eq!(frame, &[0x08, 0x00]);
+        assert_eq!(radiotap.channel.unwrap().freq, 2437);
+        assert!(radiotap.channel.unwrap().flags.cck);
+        assert_eq!(radiotap.antenna_signal, Some(AntennaSignal { value: -55 }));
+        assert!(!radiotap.flags.unwrap().preamble);
+    }
+}
@@ -0,0 +1,55 @@
+//! Converters from other 802.11 capture headers to Radiotap.
+//!
+//! Each converter can either parse its header into a
+//! [Radiotap](../struct.Radiotap.html), or rewrite a whole captured frame so
+//! that the header is replaced by a generated Radiotap header.
+//!
+//! The AVS and Prism headers also record the host time a frame was received
+//! at. Radiotap has no field for it, so it is dropped by both converters.
+
+pub mod avs;
+pub mod ppi;
+pub mod prism;
+
+use std::convert::TryFrom;
+
+use crate::{
+    channel::{channel_to_freq, Band},
+    field::{ext::ChannelFlags, Channel},
+    Radiotap, Result,
+};
+
+/// The link type for 802.11 frames without any capture header.
+pub const LINKTYPE_IEEE802_11: u32 = 105;
+
+/// Returns the Radiotap with its header and derived fields, such as the data
+/// rates, populated by round tripping it through its encoding.
+fn finish(radiotap: Radiotap) -> Result<Radiotap> {
+    Radiotap::from_bytes(&radiotap.to_bytes())
+}
+
+/// Returns the encoded Radiotap header followed by the 802.11 frame.
+fn rewrite(radiotap: &Radiotap, frame: &[u8]) -> Vec<u8> {
+    let mut out = radiotap.to_bytes();
+    out.extend_from_slice(frame);
+    out
+}
+
+/// Returns the channel for a 2.4 or 5 GHz channel number.
+fn channel(number: u32, ghz5: bool, cck: bool, ofdm: bool) -> Option<Channel> {
+    let band = if ghz5 { Band::Ghz5 } else { Band::Ghz2 };
+    let freq = channel_to_freq(u8::try_from(number).ok()?, band)?;
+    Some(Channel {
+        freq: freq as u16,
+        flags: ChannelFlags {
+            turbo: false,
+            cck,
+            ofdm,
+            ghz2: !ghz5,
+            ghz5,
+            passive: false,
+            dynamic: false,
+            gfsk: false,
+        },
+    })
+}
@@ -0,0 +1,281 @@
+//! Conversion of Per-Packet Information (PPI) headers to Radiotap.
+//!
+//! The 802.11-Common, 802.11n MAC, and 802.11n MAC+PHY extension fields are
+//! converted, all other PPI fields are ignored.
+
+use bitops::BitOps;
+use byteorder::{ReadBytesExt, LE};
+use std::io::Cursor;
+
+use crate::{
+    convert::{finish, rewrite, LINKTYPE_IEEE802_11},
+    field::{ext::*, *},
+    Error, Radiotap, Result,
+};
+
+/// The link type for 802.11 frames preceded by a PPI header.
+pub const LINKTYPE_PPI: u32 = 192;
+
+const FIELD_80211_COMMON: u16 = 2;
+const FIELD_80211N_MAC: u16 = 3;
+const FIELD_80211N_MAC_PHY: u16 = 4;
+
+/// Returns the [Radiotap](../../struct.Radiotap.html) equivalent of the PPI
+/// header at the start of the input.
+pub fn from_bytes(input: &[u8]) -> Result<Radiotap> {
+    Ok(parse(input)?.0)
+}
+
+/// Returns the [Radiotap](../../struct.Radiotap.html) equivalent of the PPI
+/// header at the start of the input, and the 802.11 frame following it.
+pub fn parse(input: &[u8]) -> Result<(Radiotap, &[u8])> {
+    let mut cursor = Cursor::new(input);
+
+    let version = cursor.read_u8()?;
+    if version != 0 {
+        return Err(Error::UnsupportedVersion);
+    }
+    let aligned = cursor.read_u8()?.is_flag_set(0x01);
+    let length = cursor.read_u16::<LE>()? as usize;
+    let dlt = cursor.read_u32::<LE>()?;
+
+    if input.len() < length || length < 8 {
+        return Err(Error::InvalidLength);
+    }
+    if dlt != LINKTYPE_IEEE802_11 {
+        return Err(Error::UnsupportedLinkType(dlt));
+    }
+
+    let (header, rest) = input.split_at(length);
+    let mut radiotap = Radiotap::default();
+    let mut offset = 8;
+
+    while offset + 4 <= header.len() {
+        let mut cursor = Cursor::new(&header[offset..]);
+        let kind = cursor.read_u16::<LE>()?;
+        let len = cursor.read_u16::<LE>()? as usize;
+        let data = header
+            .get(offset + 4..offset + 4 + len)
+            .ok_or(Error::IncompleteError)?;
+
+        match kind {
+            FIELD_80211_COMMON => common(&mut radiotap, data)?,
+            FIELD_80211N_MAC => {
+                mac(&mut radiotap, data)?;
+            }
+            FIELD_80211N_MAC_PHY => mac_phy(&mut radiotap, data)?,
+            _ => {}
+        }
+
+        offset += 4 + len;
+        if aligned {
+            offset = (offset + 3) & !3;
+        }
+    }
+
+    Ok((finish(radiotap)?, rest))
+}
+
+/// Returns the captured frame with the PPI header replaced by the equivalent
+/// Radiotap header.
+pub fn to_radiotap_frame(input: &[u8]) -> Result<Vec<u8>> {
+    let (radiotap, frame) = parse(input)?;
+    Ok(rewrite(&radiotap, frame))
+}
+
+/// Converts the 802.11-Common field.
+fn common(radiotap: &mut Radiotap, data: &[u8]) -> Result<()> {
+    let mut cursor = Cursor::new(data);
+    let tsft = cursor.read_u64::<LE>()?;
+    let flags = cursor.read_u16::<LE>()?;
+    let rate = cursor.read_u16::<LE>()?;
+    let freq = cursor.read_u16::<LE>()?;
+    let channel_flags = cursor.read_u16::<LE>()?;
+    let hopset = cursor.read_u8()?;
+    let pattern = cursor.read_u8()?;
+    let signal = cursor.read_i8()?;
+    let noise = cursor.read_i8()?;
+
+    radiotap.tsft = Some(TSFT {
+        // The TSFT may be reported in milliseconds
+        value: if flags.is_flag_set(0x0002) {
+            tsft.saturating_mul(1000)
+        } else {
+            tsft
+        },
+    });
+
+    radiotap.flags = Some(Flags {
+        cfp: false,
+        preamble: false,
+        wep: false,
+        fragmentation: false,
+        fcs: flags.is_flag_set(0x0001),
+        data_pad: false,
+        bad_fcs: flags.is_flag_set(0x0004),
+        sgi: false,
+    });
+
+    if rate != 0 {
+        radiotap.rate = Some(Rate {
+            value: f32::from(rate) / 2.0,
+        });
+    }
+
+    if freq != 0 {
+        // The PPI channel flags are the same as the Radiotap ones
+        let mut bytes = [0; 4];
+        bytes[..2].copy_from_slice(&freq.to_le_bytes());
+        bytes[2..].copy_from_slice(&channel_flags.to_le_bytes());
+        radiotap.channel = Some(Channel::from_bytes(&bytes)?);
+    }
+
+    if hopset != 0 || pattern != 0 {
+        radiotap.fhss = Some(FHSS { hopset, pattern });
+    }
+
+    // Drivers report -128 or 0 when the value is unknown
+    if signal != -128 && signal != 0 {
+        radiotap.antenna_signal = Some(AntennaSignal { value: signal });
+    }
+    if noise != -128 && noise != 0 {
+        radiotap.antenna_noise = Some(AntennaNoise { value: noise });
+    }
+
+    Ok(())
+}
+
+/// Converts the 802.11n MAC extension field.
+fn mac(radiotap: &mut Radiotap, data: &[u8]) -> Result<u32> {
+    let mut cursor = Cursor::new(data);
+    let flags = cursor.read_u32::<LE>()?;
+    let reference = cursor.read_u32::<LE>()?;
+
+    // The frame was part of an A-MPDU
+    if flags.is_flag_set(0x0010) {
+        radiotap.ampdu_status = Some(AMPDUStatus {
+            reference,
+            last: Some(!flags.is_flag_set(0x0020)),
+            ..Default::default()
+        });
+    }
+
+    Ok(flags)
+}
+
+/// Converts the 802.11n MAC+PHY extension field.
+fn mac_phy(radiotap: &mut Radiotap, data: &[u8]) -> Result<()> {
+    let flags = mac(radiotap, data)?;
+
+    let mut cursor = Cursor::new(data);
+    cursor.set_position(9);
+    let index = cursor.read_u8()?;
+    let streams = cursor.read_u8()?;
+
+    // An unknown MCS index or stream count means this was not an HT frame
+    if index == 255 || streams == 0 {
+        return Ok(());
+    }
+
+    radiotap.mcs = Some(MCS {
+        bw: Some(Bandwidth::new(u8::from(flags.is_flag_set(0x0002)))?),
+        index: Some(index),
+        gi: Some(if flags.is_flag_set(0x0004) {
+            GuardInterval::Short
+        } else {
+            GuardInterval::Long
+        }),
+        format: Some(if flags.is_flag_set(0x0001) {
+            HTFormat::Greenfield
+        } else {
+            HTFormat::Mixed
+        }),
+        ..Default::default()
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ppi(fields: &[(u16, Vec<u8>)], frame: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (kind, data) in fields {
+            body.extend_from_slice(&kind.to_le_bytes());
+            body.extend_from_slice(&(data.len() as u16).to_le_bytes());
+            body.extend_from_slice(data);
+        }
+        let mut out = vec![0, 0];
+        out.extend_from_slice(&(8 + body.len() as u16).to_le_bytes());
+        out.extend_from_slice(&LINKTYPE_IEEE802_11.to_le_bytes());
+        out.extend(body);
+        out.extend_from_slice(frame);
+        out
+    }
+
+    fn common_field() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&0x0001u16.to_le_bytes());
+        data.extend_from_slice(&12u16.to_le_bytes());
+        data.extend_from_slice(&2437u16.to_le_bytes());
+        data.extend_from_slice(&0x00c0u16.to_le_bytes());
+        data.extend_from_slice(&[0, 0, (-42i8) as u8, (-95i8) as u8]);
+        data
+    }
+
+    #[test]
+    fn common() {
+        let input = ppi(&[(FIELD_80211_COMMON, common_field())], &[0x80, 0]);
+        let (radiotap, rest) = parse(&input).unwrap();
+
+        assert_eq!(rest, &[0x80, 0]);
+        assert_eq!(radiotap.tsft, Some(TSFT { value: 1_000_000 }));
+        assert_eq!(radiotap.rate, Some(Rate { value: 6.0 }));
+        assert_eq!(radiotap.channel.unwrap().freq, 2437);
+        assert!(radiotap.channel.unwrap().flags.ghz2);
+        assert_eq!(radiotap.antenna_signal, Some(AntennaSignal { value: -42 }));
+        assert_eq!(radiotap.antenna_noise, Some(AntennaNoise { value: -95 }));
+        assert!(radiotap.flags.unwrap().fcs);
+        assert!(!radiotap.header.present.is_empty());
+    }
+
+    #[test]
+    fn mac_phy() {
+        let mut data = vec![0; 48];
+        data[0] = 0x02 | 0x04 | 0x10;
+        data[4..8].copy_from_slice(&7u32.to_le_bytes());
+        data[9] = 7;
+        data[10] = 1;
+
+        let input = ppi(
+            &[
+                (FIELD_80211_COMMON, common_field()),
+                (FIELD_80211N_MAC_PHY, data),
+            ],
+            &[],
+        );
+        let radiotap = from_bytes(&input).unwrap();
+        let mcs = radiotap.mcs.unwrap();
+
+        assert_eq!(mcs.index, Some(7));
+        assert_eq!(mcs.bw.unwrap().bandwidth, 40);
+        assert_eq!(mcs.gi, Some(GuardInterval::Short));
+        assert_eq!(mcs.datarate, Some(150.0));
+        assert_eq!(radiotap.ampdu_status.unwrap().reference, 7);
+        assert_eq!(radiotap.ampdu_status.unwrap().last, Some(true));
+    }
+
+    #[test]
+    fn bad_link_type() {
+        let mut input = ppi(&[], &[]);
+        input[4] = 1;
+
+        match parse(&input).unwrap_err() {
+            Error::UnsupportedLinkType(1) => {}
+            e => panic!("Error not UnsupportedLinkType: {:?}", e),
+        };
+    }
+}
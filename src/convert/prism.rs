@@ -0,0 +1,160 @@
+//! Conversion of Prism monitor mode headers to Radiotap.
+//!
+//! The MAC time, channel, signal, noise, and rate items are converted. Items
+//! whose status marks them as not supplied are skipped.
+
+use byteorder::{ByteOrder, BE, LE};
+
+use crate::{
+    convert::{channel, finish, rewrite},
+    field::*,
+    Error, Radiotap, Result,
+};
+
+/// The link type for 802.11 frames preceded by a Prism header.
+pub const LINKTYPE_PRISM: u32 = 119;
+
+/// The length of the Prism header.
+const LENGTH: usize = 144;
+
+/// The offset of the first item, after the message code, length, and device
+/// name.
+const ITEMS: usize = 24;
+
+/// The Prism items, in the order they appear in the header.
+const ITEM_MACTIME: usize = 1;
+const ITEM_CHANNEL: usize = 2;
+const ITEM_SIGNAL: usize = 5;
+const ITEM_NOISE: usize = 6;
+const ITEM_RATE: usize = 7;
+
+/// Returns the [Radiotap](../../struct.Radiotap.html) equivalent of the Prism
+/// header at the start of the input.
+pub fn from_bytes(input: &[u8]) -> Result<Radiotap> {
+    Ok(parse(input)?.0)
+}
+
+/// Returns the [Radiotap](../../struct.Radiotap.html) equivalent of the Prism
+/// header at the start of the input, and the 802.11 frame following it.
+pub fn parse(input: &[u8]) -> Result<(Radiotap, &[u8])> {
+    if input.len() < LENGTH {
+        return Err(Error::IncompleteError);
+    }
+
+    // The header is written in host byte order
+    let big_endian = if LE::read_u32(&input[4..]) as usize == LENGTH {
+        false
+    } else if BE::read_u32(&input[4..]) as usize == LENGTH {
+        true
+    } else {
+        return Err(Error::InvalidLength);
+    };
+
+    // Returns the item's value if its status marks it as supplied
+    let item = |index: usize| -> Option<u32> {
+        let item = &input[ITEMS + index * 12..];
+        let (status, data) = if big_endian {
+            (BE::read_u16(&item[4..]), BE::read_u32(&item[8..]))
+        } else {
+            (LE::read_u16(&item[4..]), LE::read_u32(&item[8..]))
+        };
+        if status == 0 {
+            Some(data)
+        } else {
+            None
+        }
+    };
+
+    let mut radiotap = Radiotap::default();
+
+    if let Some(mactime) = item(ITEM_MACTIME) {
+        radiotap.tsft = Some(TSFT {
+            value: u64::from(mactime),
+        });
+    }
+
+    // The band is not recorded, so assume channels above 14 are 5 GHz
+    if let Some(number) = item(ITEM_CHANNEL) {
+        radiotap.channel = channel(number, number > 14, false, false);
+    }
+
+    if let Some(signal) = item(ITEM_SIGNAL) {
+        radiotap.antenna_signal = Some(AntennaSignal {
+            value: signal as i32 as i8,
+        });
+    }
+
+    if let Some(noise) = item(ITEM_NOISE) {
+        radiotap.antenna_noise = Some(AntennaNoise {
+            value: noise as i32 as i8,
+        });
+    }
+
+    // The rate is in units of 500 kbps
+    if let Some(rate) = item(ITEM_RATE).filter(|&rate| rate != 0) {
+        radiotap.rate = Some(Rate {
+            value: rate as f32 / 2.0,
+        });
+    }
+
+    Ok((finish(radiotap)?, &input[LENGTH..]))
+}
+
+/// Returns the captured frame with the Prism header replaced by the
+/// equivalent Radiotap header.
+pub fn to_radiotap_frame(input: &[u8]) -> Result<Vec<u8>> {
+    let (radiotap, frame) = parse(input)?;
+    Ok(rewrite(&radiotap, frame))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prism(items: [(u16, u32); 10]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&0x44u32.to_le_bytes());
+        out.extend_from_slice(&(LENGTH as u32).to_le_bytes());
+        out.extend_from_slice(b"wlan0\0\0\0\0\0\0\0\0\0\0\0");
+        for (i, (status, data)) in items.iter().enumerate() {
+            out.extend_from_slice(&(0x1044 + ((i as u32) << 12)).to_le_bytes());
+            out.extend_from_slice(&status.to_le_bytes());
+            out.extend_from_slice(&4u16.to_le_bytes());
+            out.extend_from_slice(&data.to_le_bytes());
+        }
+        out.extend_from_slice(&[0x80, 0x00]);
+        out
+    }
+
+    #[test]
+    fn items() {
+        let mut items = [(1, 0); 10];
+        items[ITEM_MACTIME] = (0, 123_456);
+        items[ITEM_CHANNEL] = (0, 11);
+        items[ITEM_SIGNAL] = (0, -60i32 as u32);
+        items[ITEM_RATE] = (0, 22);
+
+        let input = prism(items);
+        let (radiotap, frame) = parse(&input).unwrap();
+
+        assert_eq!(frame, &[0x80, 0x00]);
+        assert_eq!(radiotap.tsft, Some(TSFT { value: 123_456 }));
+        assert_eq!(radiotap.channel.unwrap().freq, 2462);
+        assert_eq!(radiotap.antenna_signal, Some(AntennaSignal { value: -60 }));
+        assert_eq!(radiotap.antenna_noise, None);
+        assert_eq!(radiotap.rate, Some(Rate { value: 11.0 }));
+    }
+
+    #[test]
+    fn radiotap_frame() {
+        let mut items = [(1, 0); 10];
+        items[ITEM_CHANNEL] = (0, 149);
+
+        let output = to_radiotap_frame(&prism(items)).unwrap();
+        let (radiotap, frame) = Radiotap::parse(&output).unwrap();
+
+        assert_eq!(frame, &[0x80, 0x00]);
+        assert_eq!(radiotap.channel.unwrap().freq, 5745);
+        assert_eq!(radiotap.tsft, None);
+    }
+}
@@ -0,0 +1,234 @@
+//! Export of parsed Radiotap headers as CSV or JSON Lines.
+//!
+//! Each member of the [schema](../schema/index.html) is flattened into its own
+//! column, named by its path joined with underscores, such as `channel_freq`
+//! or `vht_user0_nss`. The columns are always in the same order, and absent
+//! fields are written as empty cells in CSV and as `null` in JSON Lines.
+//!
+//! ```
+//! use radiotap::{
+//!     export::{Format, Writer},
+//!     field::TSFT,
+//!     Radiotap,
+//! };
+//!
+//! let radiotap = Radiotap {
+//!     tsft: Some(TSFT { value: 1234 }),
+//!     ..Default::default()
+//! };
+//!
+//! let mut writer = Writer::with_columns(Vec::new(), Format::Csv, &["tsft", "rate"]).unwrap();
+//! writer.write(&radiotap).unwrap();
+//! let csv = writer.into_inner().unwrap();
+//! assert_eq!(csv, b"tsft,rate\n1234,\n");
+//! ```
+
+use std::io::Write;
+
+use crate::{
+    schema::{self, Member, Value},
+    Error, Radiotap, Result,
+};
+
+/// The format of the exported rows.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Format {
+    /// Comma separated values with a header row.
+    Csv,
+    /// A JSON object per line.
+    JsonLines,
+}
+
+/// Returns the column name of a member path, such as `vht_user0_nss` for
+/// `vht.users[0].nss`.
+fn column_name(path: &str) -> String {
+    path.replace("users[", "user")
+        .replace(']', "")
+        .replace('.', "_")
+}
+
+/// Returns the names of all columns in their stable order.
+pub fn columns() -> impl Iterator<Item = String> {
+    schema::members()
+        .iter()
+        .map(|member| column_name(member.path()))
+}
+
+/// Writes Radiotap headers as rows of CSV or JSON Lines.
+#[derive(Debug)]
+pub struct Writer<W> {
+    writer: W,
+    format: Format,
+    columns: Vec<(String, &'static Member)>,
+}
+
+impl<W: Write> Writer<W> {
+    /// Creates a new writer with all columns, writing the header row for CSV.
+    pub fn new(writer: W, format: Format) -> Result<Writer<W>> {
+        let columns = schema::members()
+            .iter()
+            .map(|member| (column_name(member.path()), member))
+            .collect();
+        Writer::create(writer, format, columns)
+    }
+
+    /// Creates a new writer with the given columns, in the given order.
+    pub fn with_columns(writer: W, format: Format, names: &[&str]) -> Result<Writer<W>> {
+        let columns = names
+            .iter()
+            .map(|name| {
+                schema::members()
+                    .iter()
+                    .find(|member| column_name(member.path()) == *name)
+                    .map(|member| (name.to_string(), member))
+                    .ok_or_else(|| Error::UnknownColumn(name.to_string()))
+            })
+            .collect::<Result<_>>()?;
+        Writer::create(writer, format, columns)
+    }
+
+    fn create(
+        mut writer: W,
+        format: Format,
+        columns: Vec<(String, &'static Member)>,
+    ) -> Result<Writer<W>> {
+        if format == Format::Csv {
+            let names: Vec<_> = columns.iter().map(|(name, _)| name.as_str()).collect();
+            writeln!(writer, "{}", names.join(","))?;
+        }
+        Ok(Writer {
+            writer,
+            format,
+            columns,
+        })
+    }
+
+    /// Writes a row for a Radiotap header.
+    pub fn write(&mut self, radiotap: &Radiotap) -> Result<()> {
+        let mut row = String::new();
+        if self.format == Format::JsonLines {
+            row.push('{');
+        }
+
+        for (n, (name, member)) in self.columns.iter().enumerate() {
+            // Infinite and NaN values are not valid in JSON
+            let value = member.get(radiotap).filter(|value| match value {
+                Value::Float(f) => f.is_finite(),
+                _ => true,
+            });
+
+            match self.format {
+                Format::Csv => {
+                    if n > 0 {
+                        row.push(',');
+                    }
+                    if let Some(value) = value {
+                        row.push_str(&value.to_string());
+                    }
+                }
+                Format::JsonLines => {
+                    if n > 0 {
+                        row.push(',');
+                    }
+                    row.push('"');
+                    row.push_str(name);
+                    row.push_str("\":");
+                    match value {
+                        // Variant names never need escaping
+                        Some(Value::Str(value)) => {
+                            row.push('"');
+                            row.push_str(value);
+                            row.push('"');
+                        }
+                        Some(value) => row.push_str(&value.to_string()),
+                        None => row.push_str("null"),
+                    }
+                }
+            }
+        }
+
+        if self.format == Format::JsonLines {
+            row.push('}');
+        }
+        row.push('\n');
+        self.writer.write_all(row.as_bytes())?;
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field::TSFT, tests::vht_radiotap};
+
+    #[test]
+    fn csv() {
+        let mut writer = Writer::new(Vec::new(), Format::Csv).unwrap();
+        writer.write(&vht_radiotap()).unwrap();
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+
+        let header: Vec<_> = lines[0].split(',').collect();
+        assert_eq!(header, columns().collect::<Vec<_>>());
+        assert_eq!(header.len(), schema::members().len());
+        for name in &[
+            "mcs_gi",
+            "mcs_format",
+            "vht_bw_sideband",
+            "timestamp_position",
+        ] {
+            assert!(header.contains(name), "{} is missing", name);
+        }
+        let row: Vec<_> = lines[1].split(',').collect();
+        assert_eq!(row.len(), header.len());
+
+        let get = |name| row[header.iter().position(|&h| h == name).unwrap()];
+        assert_eq!(get("tsft"), "2593857465");
+        assert_eq!(get("antenna_signal"), "-43");
+        assert_eq!(get("channel_freq"), "5500");
+        assert_eq!(get("vht_user0_nss"), "3");
+        assert_eq!(get("vht_user0_fec"), "BCC");
+        assert_eq!(get("vht_user0_datarate"), "877.5");
+        assert_eq!(get("vht_user1_nss"), "");
+        assert_eq!(get("rate"), "");
+    }
+
+    #[test]
+    fn large_values() {
+        let radiotap = Radiotap {
+            tsft: Some(TSFT { value: u64::MAX }),
+            ..Default::default()
+        };
+        let mut writer = Writer::with_columns(Vec::new(), Format::Csv, &["tsft"]).unwrap();
+        writer.write(&radiotap).unwrap();
+        assert_eq!(
+            writer.into_inner().unwrap(),
+            b"tsft\n18446744073709551615\n"
+        );
+    }
+
+    #[test]
+    fn json_lines() {
+        let names = ["vht_user0_index", "tsft", "vht_user0_fec", "mcs_index"];
+        let mut writer = Writer::with_columns(Vec::new(), Format::JsonLines, &names).unwrap();
+        writer.write(&vht_radiotap()).unwrap();
+        writer.write(&Radiotap::default()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner().unwrap()).unwrap(),
+            "{\"vht_user0_index\":7,\"tsft\":2593857465,\"vht_user0_fec\":\"BCC\",\"mcs_index\":null}\n\
+             {\"vht_user0_index\":null,\"tsft\":null,\"vht_user0_fec\":null,\"mcs_index\":null}\n"
+        );
+
+        match Writer::with_columns(Vec::new(), Format::Csv, &["channel_frequency"]) {
+            Err(Error::UnknownColumn(name)) => assert_eq!(name, "channel_frequency"),
+            other => panic!("Error not UnknownColumn: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Serializers for Radiotap fields, the inverse of the `Field` parsers.
+
+use crate::field::{ext::*, *};
+
+/// A trait to serialize a field into its Radiotap representation.
+pub(crate) trait Encode {
+    /// Appends the encoded field to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+/// Returns `bit` if `value` is true, else 0.
+fn flag<T: Default>(value: bool, bit: T) -> T {
+    if value {
+        bit
+    } else {
+        T::default()
+    }
+}
+
+impl Encode for TSFT {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.value.to_le_bytes());
+    }
+}
+
+impl Encode for Flags {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(
+            flag(self.cfp, 0x01)
+                | flag(self.preamble, 0x02)
+                | flag(self.wep, 0x04)
+                | flag(self.fragmentation, 0x08)
+                | flag(self.fcs, 0x10)
+                | flag(self.data_pad, 0x20)
+                | flag(self.bad_fcs, 0x40)
+                | flag(self.sgi, 0x80),
+        );
+    }
+}
+
+impl Encode for Rate {
+    fn encode(&self, out: &mut Vec<u8>) {
+        // Truncating also round trips rates that were parsed as negative
+        out.push((self.value * 2.0).round() as i32 as u8);
+    }
+}
+
+impl Encode for Channel {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let flags = &self.flags;
+        let flags: u16 = flag(flags.turbo, 0x0010)
+            | flag(flags.cck, 0x0020)
+            | flag(flags.ofdm, 0x0040)
+            | flag(flags.ghz2, 0x0080)
+            | flag(flags.ghz5, 0x0100)
+            | flag(flags.passive, 0x0200)
+            | flag(flags.dynamic, 0x0400)
+            | flag(flags.gfsk, 0x0800);
+        out.extend_from_slice(&self.freq.to_le_bytes());
+        out.extend_from_slice(&flags.to_le_bytes());
+    }
+}
+
+impl Encode for FHSS {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.hopset);
+        out.push(self.pattern);
+    }
+}
+
+macro_rules! impl_encode_value {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.value.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_value!(
+    AntennaSignal,
+    AntennaSignalDb,
+    AntennaNoise,
+    AntennaNoiseDb,
+    LockQuality,
+    TxAttenuation,
+    TxAttenuationDb,
+    TxPower,
+    Antenna,
+    RTSRetries,
+    DataRetries
+);
+
+impl Encode for RxFlags {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&flag(self.bad_plcp, 0x0002u16).to_le_bytes());
+    }
+}
+
+impl Encode for TxFlags {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let flags: u16 = flag(self.fail, 0x0001)
+            | flag(self.cts, 0x0002)
+            | flag(self.rts, 0x0004)
+            | flag(self.no_ack, 0x0008)
+            | flag(self.no_seq, 0x0010);
+        out.extend_from_slice(&flags.to_le_bytes());
+    }
+}
+
+impl Encode for XChannel {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let flags = &self.flags;
+        let flags: u32 = flag(flags.turbo, 0x0000_0010)
+            | flag(flags.cck, 0x0000_0020)
+            | flag(flags.ofdm, 0x0000_0040)
+            | flag(flags.ghz2, 0x0000_0080)
+            | flag(flags.ghz5, 0x0000_0100)
+            | flag(flags.passive, 0x0000_0200)
+            | flag(flags.dynamic, 0x0000_0400)
+            | flag(flags.gfsk, 0x0000_0800)
+            | flag(flags.gsm, 0x0000_1000)
+            | flag(flags.sturbo, 0x0000_2000)
+            | flag(flags.half, 0x0000_4000)
+            | flag(flags.quarter, 0x0000_8000)
+            | flag(flags.ht20, 0x0001_0000)
+            | flag(flags.ht40u, 0x0002_0000)
+            | flag(flags.ht40d, 0x0004_0000);
+        out.extend_from_slice(&flags.to_le_bytes());
+        out.extend_from_slice(&self.freq.to_le_bytes());
+        out.push(self.channel);
+        out.push(self.max_power);
+    }
+}
+
+impl Encode for MCS {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let mut known = 0;
+        let mut flags = 0;
+
+        if let Some(bw) = self.bw.and_then(|bw| bw.value()) {
+            known |= 0x01;
+            flags |= bw & 0x03;
+        }
+        if self.index.is_some() {
+            known |= 0x02;
+        }
+        if let Some(gi) = self.gi {
+            known |= 0x04;
+            flags |= flag(gi == GuardInterval::Short, 0x04);
+        }
+        if let Some(format) = self.format {
+            known |= 0x08;
+            flags |= flag(format == HTFormat::Greenfield, 0x08);
+        }
+        if let Some(fec) = self.fec {
+            known |= 0x10;
+            flags |= flag(fec == FEC::LDPC, 0x10);
+        }
+        if let Some(stbc) = self.stbc {
+            known |= 0x20;
+            flags |= (stbc & 0x03) << 5;
+        }
+        if let Some(ness) = self.ness {
+            known |= 0x40 | (ness & 0x02) << 6;
+            flags |= (ness & 0x01) << 7;
+        }
+
+        out.push(known);
+        out.push(flags);
+        out.push(self.index.unwrap_or(0));
+    }
+}
+
+impl Encode for AMPDUStatus {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let mut flags = 0u16;
+
+        if let Some(zero_length) = self.zero_length {
+            flags |= 0x0001 | flag(zero_length, 0x0002);
+        }
+        if let Some(last) = self.last {
+            flags |= 0x0004 | flag(last, 0x0008);
+        }
+        if self.delimiter_crc.is_some() {
+            flags |= 0x0020;
+        }
+
+        out.extend_from_slice(&self.reference.to_le_bytes());
+        out.extend_from_slice(&flags.to_le_bytes());
+        out.push(self.delimiter_crc.unwrap_or(0));
+        out.push(0);
+    }
+}
+
+impl Encode for VHT {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let mut known = 0u16;
+        let mut flags = 0u8;
+        let mut bandwidth = 0;
+
+        let bits = [
+            (self.stbc, 0x01),
+            (self.txop_ps, 0x02),
+            (self.gi.map(|gi| gi == GuardInterval::Short), 0x04),
+            (self.sgi_nsym_da, 0x08),
+            (self.ldpc_extra, 0x10),
+            (self.beamformed, 0x20),
+        ];
+        for (value, bit) in bits.iter() {
+            if let Some(value) = value {
+                known |= u16::from(*bit);
+                flags |= flag(*value, *bit);
+            }
+        }
+
+        if let Some(bw) = self.bw.and_then(|bw| bw.value()) {
+            known |= 0x0040;
+            bandwidth = bw;
+        }
+        if self.group_id.is_some() {
+            known |= 0x0080;
+        }
+        if self.partial_aid.is_some() {
+            known |= 0x0100;
+        }
+
+        let mut mcs_nss = [0; 4];
+        let mut coding = 0;
+        for (i, user) in self.users.iter().enumerate() {
+            if let Some(user) = user {
+                mcs_nss[i] = user.index << 4 | (user.nss & 0x0f);
+                coding |= flag(user.fec == FEC::LDPC, 1 << i);
+            }
+        }
+
+        out.extend_from_slice(&known.to_le_bytes());
+        out.push(flags);
+        out.push(bandwidth);
+        out.extend_from_slice(&mcs_nss);
+        out.push(coding);
+        out.push(self.group_id.unwrap_or(0));
+        out.extend_from_slice(&self.partial_aid.unwrap_or(0).to_le_bytes());
+    }
+}
+
+impl Encode for Timestamp {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let unit = match self.unit {
+            TimeUnit::Milliseconds => 0,
+            TimeUnit::Microseconds => 1,
+            TimeUnit::Nanoseconds => 2,
+        };
+        let position = match self.position {
+            SamplingPosition::StartMPDU => 0,
+            SamplingPosition::StartPLCP => 1,
+            SamplingPosition::EndPPDU => 2,
+            SamplingPosition::EndMPDU => 3,
+            SamplingPosition::Unknown => 15,
+        };
+
+        out.extend_from_slice(&self.timestamp.to_le_bytes());
+        out.extend_from_slice(&self.accuracy.unwrap_or(0).to_le_bytes());
+        out.push(position << 4 | unit);
+        out.push(flag(self.accuracy.is_some(), 0x02));
+    }
+}
+
+impl Bandwidth {
+    /// Returns the Radiotap encoding of the bandwidth.
+    fn value(self) -> Option<u8> {
+        (0..=25).find(|&value| Bandwidth::new(value).ok() == Some(self))
+    }
+}
@@ -1,6 +1,6 @@
 //! Extended Radiotap field definitions and parsers.
 
-use crate::{Error, Result};
+use crate::{field::rate, Error, Result};
 
 const HT_RATE: [[f32; 4]; 32] = [
     // 20 MHz LGI,20 MHz SGI,40 MHZ LGI,40 MHz SGI
@@ -166,6 +166,19 @@ pub fn vht_rate(index: u8, bw: Bandwidth, gi: GuardInterval, nss: u8) -> Result<
     Ok(rate)
 }
 
+/// Returns the 802.11ax data rate in Mbps based on the MCS index, resource
+/// unit size, guard interval, number of spatial streams, and whether dual
+/// carrier modulation is used.
+pub fn he_rate(index: u8, ru: RUSize, gi: HEGuardInterval, nss: u8, dcm: bool) -> Result<f32> {
+    Ok(rate::he(index, ru, gi, nss, dcm)?.mbps())
+}
+
+/// Returns the 802.11be data rate in Mbps based on the MCS index, resource
+/// unit size, guard interval, and number of spatial streams.
+pub fn eht_rate(index: u8, ru: RUSize, gi: HEGuardInterval, nss: u8) -> Result<f32> {
+    Ok(rate::eht(index, ru, gi, nss)?.mbps())
+}
+
 /// Flags describing the channel.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct ChannelFlags {
@@ -358,3 +371,75 @@ impl SamplingPosition {
         })
     }
 }
+
+/// The guard interval of an HE or EHT PPDU.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HEGuardInterval {
+    /// 0.8 us.
+    Ns800,
+    /// 1.6 us.
+    Ns1600,
+    /// 3.2 us.
+    Ns3200,
+}
+
+impl HEGuardInterval {
+    /// Returns the guard interval in nanoseconds.
+    pub fn nanos(self) -> u32 {
+        match self {
+            HEGuardInterval::Ns800 => 800,
+            HEGuardInterval::Ns1600 => 1600,
+            HEGuardInterval::Ns3200 => 3200,
+        }
+    }
+}
+
+/// The size of an HE or EHT resource unit.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RUSize {
+    /// 26 tones, the smallest resource unit.
+    Tones26,
+    /// 52 tones.
+    Tones52,
+    /// 106 tones.
+    Tones106,
+    /// 242 tones, a whole 20 MHz channel.
+    Tones242,
+    /// 484 tones, a whole 40 MHz channel.
+    Tones484,
+    /// 996 tones, a whole 80 MHz channel.
+    Tones996,
+    /// 2x996 tones, a whole 160 MHz channel.
+    Tones2x996,
+    /// 4x996 tones, a whole 320 MHz channel, only used by EHT.
+    Tones4x996,
+}
+
+impl RUSize {
+    /// Returns the resource unit covering a whole PPDU of the given bandwidth
+    /// in MHz.
+    pub fn from_bandwidth(bandwidth: u16) -> Result<RUSize> {
+        Ok(match bandwidth {
+            20 => RUSize::Tones242,
+            40 => RUSize::Tones484,
+            80 => RUSize::Tones996,
+            160 => RUSize::Tones2x996,
+            320 => RUSize::Tones4x996,
+            _ => return Err(Error::InvalidFormat),
+        })
+    }
+
+    /// Returns the number of data subcarriers in the resource unit.
+    pub fn data_subcarriers(self) -> u16 {
+        match self {
+            RUSize::Tones26 => 24,
+            RUSize::Tones52 => 48,
+            RUSize::Tones106 => 102,
+            RUSize::Tones242 => 234,
+            RUSize::Tones484 => 468,
+            RUSize::Tones996 => 980,
+            RUSize::Tones2x996 => 1960,
+            RUSize::Tones4x996 => 3920,
+        }
+    }
+}
@@ -1,6 +1,8 @@
 //! Radiotap field definitions and parsers.
 
+pub(crate) mod encode;
 pub mod ext;
+pub mod rate;
 
 use bitops::BitOps;
 use byteorder::{ReadBytesExt, LE};
@@ -71,6 +73,37 @@ impl Kind {
         })
     }
 
+    /// Returns the bit number of the field in the `present` word, or `None`
+    /// for a vendor namespace.
+    pub fn value(self) -> Option<u8> {
+        Some(match self {
+            Kind::TSFT => 0,
+            Kind::Flags => 1,
+            Kind::Rate => 2,
+            Kind::Channel => 3,
+            Kind::FHSS => 4,
+            Kind::AntennaSignal => 5,
+            Kind::AntennaNoise => 6,
+            Kind::LockQuality => 7,
+            Kind::TxAttenuation => 8,
+            Kind::TxAttenuationDb => 9,
+            Kind::TxPower => 10,
+            Kind::Antenna => 11,
+            Kind::AntennaSignalDb => 12,
+            Kind::AntennaNoiseDb => 13,
+            Kind::RxFlags => 14,
+            Kind::TxFlags => 15,
+            Kind::RTSRetries => 16,
+            Kind::DataRetries => 17,
+            Kind::XChannel => 18,
+            Kind::MCS => 19,
+            Kind::AMPDUStatus => 20,
+            Kind::VHT => 21,
+            Kind::Timestamp => 22,
+            Kind::VendorNamespace(_) => return None,
+        })
+    }
+
     /// Returns the align value for the field.
     pub fn align(self) -> u64 {
         match self {
@@ -143,6 +176,17 @@ pub struct Header {
     pub present: Vec<Kind>,
 }
 
+impl Header {
+    /// Returns the bit numbers of the fields present in the Radiotap namespace
+    /// of a capture that are not supported, and therefore not parsed.
+    pub fn unsupported(input: &[u8]) -> Result<Vec<u8>> {
+        Header::from_bytes(input)?;
+        let mut cursor = Cursor::new(input);
+        cursor.set_position(4);
+        Ok(read_present(&mut cursor)?.1)
+    }
+}
+
 impl Field for Header {
     fn from_bytes(input: &[u8]) -> Result<Header> {
         let mut cursor = Cursor::new(input);
@@ -160,52 +204,7 @@ impl Field for Header {
             return Err(Error::InvalidLength);
         }
 
-        let mut present;
-        let mut present_count = 0;
-        let mut vendor_namespace = false;
-        let mut kinds = Vec::new();
-
-        loop {
-            present = cursor.read_u32::<LE>()?;
-
-            if !vendor_namespace {
-                for bit in 0..29 {
-                    if present.is_bit_set(bit) {
-                        match Kind::new(present_count * 32 + bit) {
-                            Ok(kind) => {
-                                kinds.push(kind);
-                            }
-                            Err(Error::UnsupportedField) => {
-                                // Does not matter, we will just parse the ones we can
-                            }
-                            Err(e) => return Err(e),
-                        }
-                    }
-                }
-            }
-
-            // Need to move to radiotap namespace
-            if present.is_bit_set(29) {
-                present_count = 0;
-                vendor_namespace = false;
-
-            // Need to move to vendor namespace
-            } else if present.is_bit_set(30) {
-                present_count = 0;
-                vendor_namespace = true;
-                // We'll figure out what namespace it is later, just use none
-                kinds.push(Kind::VendorNamespace(None))
-
-            // Need to stay in the same namespace
-            } else {
-                present_count += 1;
-            }
-
-            // More present words do not exist
-            if !present.is_bit_set(31) {
-                break;
-            }
-        }
+        let (kinds, _) = read_present(&mut cursor)?;
 
         Ok(Header {
             version,
@@ -216,6 +215,61 @@ impl Field for Header {
     }
 }
 
+/// Reads the present words, returning the kinds of the fields present and the
+/// bit numbers of the unsupported fields in the Radiotap namespace.
+fn read_present(cursor: &mut Cursor<&[u8]>) -> Result<(Vec<Kind>, Vec<u8>)> {
+    let mut present;
+    let mut present_count = 0;
+    let mut vendor_namespace = false;
+    let mut kinds = Vec::new();
+    let mut unsupported = Vec::new();
+
+    loop {
+        present = cursor.read_u32::<LE>()?;
+
+        if !vendor_namespace {
+            for bit in 0..29 {
+                if present.is_bit_set(bit) {
+                    match Kind::new(present_count * 32 + bit) {
+                        Ok(kind) => {
+                            kinds.push(kind);
+                        }
+                        Err(Error::UnsupportedField) => {
+                            // Does not matter, we will just parse the ones we can
+                            unsupported.push(present_count * 32 + bit);
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        }
+
+        // Need to move to radiotap namespace
+        if present.is_bit_set(29) {
+            present_count = 0;
+            vendor_namespace = false;
+
+        // Need to move to vendor namespace
+        } else if present.is_bit_set(30) {
+            present_count = 0;
+            vendor_namespace = true;
+            // We'll figure out what namespace it is later, just use none
+            kinds.push(Kind::VendorNamespace(None))
+
+        // Need to stay in the same namespace
+        } else {
+            present_count += 1;
+        }
+
+        // More present words do not exist
+        if !present.is_bit_set(31) {
+            break;
+        }
+    }
+
+    Ok((kinds, unsupported))
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct VendorNamespace {
     pub oui: Oui,
@@ -668,8 +722,8 @@ impl Field for MCS {
             mcs.ness = Some(known & 0x80 >> 6 | flags & 0x80 >> 7)
         }
 
-        if mcs.bw.is_some() && mcs.gi.is_some() {
-            mcs.datarate = Some(ht_rate(index, mcs.bw.unwrap(), mcs.gi.unwrap())?);
+        if let (Some(bw), Some(gi)) = (mcs.bw, mcs.gi) {
+            mcs.datarate = Some(ht_rate(index, bw, gi)?);
         }
 
         Ok(mcs)
@@ -813,10 +867,9 @@ impl Field for VHT {
             let nsts = nss << (flags & 0x01);
             let id = i as u8;
 
-            let datarate = if vht.bw.is_some() && vht.gi.is_some() {
-                Some(vht_rate(index, vht.bw.unwrap(), vht.gi.unwrap(), nss)?)
-            } else {
-                None
+            let datarate = match (vht.bw, vht.gi) {
+                (Some(bw), Some(gi)) => Some(vht_rate(index, bw, gi, nss)?),
+                _ => None,
             };
 
             vht.users[id as usize] = Some(VHTUser {
@@ -856,7 +909,7 @@ impl Field for Timestamp {
         let mut accuracy = Some(cursor.read_u16::<LE>()?);
         let unit_position = cursor.read_u8()?;
         let unit = TimeUnit::new(unit_position & 0x0f)?;
-        let position = SamplingPosition::from(unit_position & 0xf0 >> 4)?;
+        let position = SamplingPosition::from((unit_position & 0xf0) >> 4)?;
         let flags = cursor.read_u8()?;
 
         if !flags.is_flag_set(0x02) {
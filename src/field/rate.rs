@@ -0,0 +1,324 @@
+//! Data rate calculation for the HT, VHT, HE, and EHT PHYs.
+//!
+//! Rates are computed from the number of data subcarriers, the bits per
+//! subcarrier, the coding rate, the number of spatial streams, and the symbol
+//! duration, rather than looked up. They are exact to the kbps, unlike the
+//! rounded table values of [ht_rate](../ext/fn.ht_rate.html) and
+//! [vht_rate](../ext/fn.vht_rate.html) that the MCS and VHT fields report.
+
+use std::fmt;
+
+use crate::{
+    field::ext::{Bandwidth, GuardInterval, HEGuardInterval, RUSize},
+    Error, Result,
+};
+
+/// The modulation of each subcarrier.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Modulation {
+    /// Binary phase shift keying, 1 bit per subcarrier.
+    BPSK,
+    /// Quadrature phase shift keying, 2 bits per subcarrier.
+    QPSK,
+    /// 16-QAM, 4 bits per subcarrier.
+    QAM16,
+    /// 64-QAM, 6 bits per subcarrier.
+    QAM64,
+    /// 256-QAM, 8 bits per subcarrier, from VHT on.
+    QAM256,
+    /// 1024-QAM, 10 bits per subcarrier, from HE on.
+    QAM1024,
+    /// 4096-QAM, 12 bits per subcarrier, only used by EHT.
+    QAM4096,
+}
+
+impl Modulation {
+    /// Returns the number of coded bits per subcarrier.
+    pub fn bits(self) -> u32 {
+        match self {
+            Modulation::BPSK => 1,
+            Modulation::QPSK => 2,
+            Modulation::QAM16 => 4,
+            Modulation::QAM64 => 6,
+            Modulation::QAM256 => 8,
+            Modulation::QAM1024 => 10,
+            Modulation::QAM4096 => 12,
+        }
+    }
+}
+
+impl fmt::Display for Modulation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Modulation::BPSK => "BPSK",
+            Modulation::QPSK => "QPSK",
+            Modulation::QAM16 => "16-QAM",
+            Modulation::QAM64 => "64-QAM",
+            Modulation::QAM256 => "256-QAM",
+            Modulation::QAM1024 => "1024-QAM",
+            Modulation::QAM4096 => "4096-QAM",
+        })
+    }
+}
+
+/// The forward error correction coding rate.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CodingRate {
+    /// The number of data bits in each group of coded bits.
+    pub numerator: u32,
+    /// The number of coded bits in each group.
+    pub denominator: u32,
+}
+
+impl fmt::Display for CodingRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// The modulation and coding rate for each HT, VHT, HE, and EHT MCS index.
+/// HT MCS indices repeat these for each number of spatial streams.
+const MCS: [(Modulation, u32, u32); 14] = [
+    (Modulation::BPSK, 1, 2),
+    (Modulation::QPSK, 1, 2),
+    (Modulation::QPSK, 3, 4),
+    (Modulation::QAM16, 1, 2),
+    (Modulation::QAM16, 3, 4),
+    (Modulation::QAM64, 2, 3),
+    (Modulation::QAM64, 3, 4),
+    (Modulation::QAM64, 5, 6),
+    (Modulation::QAM256, 3, 4),
+    (Modulation::QAM256, 5, 6),
+    (Modulation::QAM1024, 3, 4),
+    (Modulation::QAM1024, 5, 6),
+    (Modulation::QAM4096, 3, 4),
+    (Modulation::QAM4096, 5, 6),
+];
+
+/// A data rate and how it was derived.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DataRate {
+    /// The data rate in kbps, rounded to the nearest kbps.
+    pub kbps: u64,
+    /// The modulation of each subcarrier.
+    pub modulation: Modulation,
+    /// The coding rate.
+    pub coding_rate: CodingRate,
+    /// The number of data subcarriers.
+    pub data_subcarriers: u32,
+    /// The number of spatial streams.
+    pub nss: u8,
+    /// The symbol duration including the guard interval in nanoseconds.
+    pub symbol_ns: u32,
+}
+
+impl DataRate {
+    /// Returns the data rate for an MCS index from the shared HT, VHT, HE, and
+    /// EHT modulation and coding table.
+    pub fn new(index: u8, data_subcarriers: u32, nss: u8, symbol_ns: u32) -> Result<DataRate> {
+        let (modulation, numerator, denominator) =
+            *MCS.get(index as usize).ok_or(Error::InvalidFormat)?;
+        if nss == 0 || symbol_ns == 0 {
+            return Err(Error::InvalidFormat);
+        }
+
+        // Data bits per symbol divided by the symbol duration in ns, in kbps
+        let bits = u64::from(data_subcarriers * modulation.bits() * u32::from(nss) * numerator);
+        let divisor = u64::from(denominator) * u64::from(symbol_ns);
+        let kbps = (bits * 1_000_000 + divisor / 2) / divisor;
+
+        Ok(DataRate {
+            kbps,
+            modulation,
+            coding_rate: CodingRate {
+                numerator,
+                denominator,
+            },
+            data_subcarriers,
+            nss,
+            symbol_ns,
+        })
+    }
+
+    /// Returns the number of data bits carried by each symbol.
+    pub fn bits_per_symbol(&self) -> u64 {
+        let coded = self.data_subcarriers * self.modulation.bits() * u32::from(self.nss);
+        u64::from(coded * self.coding_rate.numerator / self.coding_rate.denominator)
+    }
+
+    /// Returns the data rate in Mbps.
+    pub fn mbps(&self) -> f32 {
+        self.kbps as f32 / 1000.0
+    }
+
+    /// Returns the name of the modulation and coding, such as "64-QAM 5/6".
+    pub fn name(&self) -> String {
+        format!("{} {}", self.modulation, self.coding_rate)
+    }
+}
+
+/// Returns the 802.11n data rate based on the MCS index, bandwidth, and guard
+/// interval.
+pub fn ht(index: u8, bw: Bandwidth, gi: GuardInterval) -> Result<DataRate> {
+    // Only the equal modulation MCS indices are supported
+    if index > 31 {
+        return Err(Error::InvalidFormat);
+    }
+    let data_subcarriers = match bw.bandwidth {
+        20 => 52,
+        40 => 108,
+        _ => return Err(Error::InvalidFormat),
+    };
+    DataRate::new(index % 8, data_subcarriers, index / 8 + 1, ht_symbol_ns(gi))
+}
+
+/// Returns the 802.11ac data rate based on the MCS index, bandwidth, guard
+/// interval, and number of spatial streams.
+///
+/// Combinations that the standard excludes because the coded bits do not
+/// divide evenly between the encoders are rejected.
+pub fn vht(index: u8, bw: Bandwidth, gi: GuardInterval, nss: u8) -> Result<DataRate> {
+    if index > 9 || nss > 8 {
+        return Err(Error::InvalidFormat);
+    }
+    let data_subcarriers = match bw.bandwidth {
+        20 => 52,
+        40 => 108,
+        80 => 234,
+        160 => 468,
+        _ => return Err(Error::InvalidFormat),
+    };
+    let excluded = match (bw.bandwidth, index) {
+        (20, 9) => nss != 3 && nss != 6,
+        (80, 6) => nss == 3 || nss == 7,
+        (80, 9) => nss == 6,
+        (160, 9) => nss == 3,
+        _ => false,
+    };
+    if excluded {
+        return Err(Error::InvalidFormat);
+    }
+    DataRate::new(index, data_subcarriers, nss, ht_symbol_ns(gi))
+}
+
+/// Returns the 802.11ax data rate based on the MCS index, resource unit size,
+/// guard interval, number of spatial streams, and whether dual carrier
+/// modulation is used.
+pub fn he(index: u8, ru: RUSize, gi: HEGuardInterval, nss: u8, dcm: bool) -> Result<DataRate> {
+    if index > 11 || nss > 8 || ru == RUSize::Tones4x996 {
+        return Err(Error::InvalidFormat);
+    }
+    // DCM is only defined for MCS 0, 1, 3, and 4
+    if dcm && !matches!(index, 0 | 1 | 3 | 4) {
+        return Err(Error::InvalidFormat);
+    }
+
+    let mut rate = DataRate::new(
+        index,
+        u32::from(ru.data_subcarriers()),
+        nss,
+        he_symbol_ns(gi),
+    )?;
+
+    // The same data is modulated onto both halves of the subcarriers
+    if dcm {
+        rate.data_subcarriers /= 2;
+        rate.kbps = DataRate::new(index, rate.data_subcarriers, nss, rate.symbol_ns)?.kbps;
+    }
+
+    Ok(rate)
+}
+
+/// Returns the 802.11be data rate based on the MCS index, resource unit size,
+/// guard interval, and number of spatial streams.
+pub fn eht(index: u8, ru: RUSize, gi: HEGuardInterval, nss: u8) -> Result<DataRate> {
+    if index > 13 || nss > 8 {
+        return Err(Error::InvalidFormat);
+    }
+    DataRate::new(
+        index,
+        u32::from(ru.data_subcarriers()),
+        nss,
+        he_symbol_ns(gi),
+    )
+}
+
+fn ht_symbol_ns(gi: GuardInterval) -> u32 {
+    match gi {
+        GuardInterval::Long => 4000,
+        GuardInterval::Short => 3600,
+    }
+}
+
+fn he_symbol_ns(gi: HEGuardInterval) -> u32 {
+    12_800 + gi.nanos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bw(value: u8) -> Bandwidth {
+        Bandwidth::new(value).unwrap()
+    }
+
+    #[test]
+    fn ht_rates() {
+        let rate = ht(7, bw(0), GuardInterval::Long).unwrap();
+        assert_eq!(rate.kbps, 65_000);
+        assert_eq!(rate.name(), "64-QAM 5/6");
+
+        assert_eq!(ht(31, bw(1), GuardInterval::Short).unwrap().kbps, 600_000);
+        assert_eq!(ht(1, bw(0), GuardInterval::Short).unwrap().kbps, 14_444);
+    }
+
+    #[test]
+    fn vht_rates() {
+        let rate = vht(9, bw(4), GuardInterval::Short, 1).unwrap();
+        assert_eq!(rate.kbps, 433_333);
+        assert_eq!(rate.name(), "256-QAM 5/6");
+
+        assert_eq!(vht(9, bw(0), GuardInterval::Long, 3).unwrap().kbps, 260_000);
+        assert_eq!(
+            vht(9, bw(11), GuardInterval::Short, 8).unwrap().kbps,
+            6_933_333
+        );
+
+        // Excluded by the standard
+        assert!(vht(9, bw(0), GuardInterval::Long, 1).is_err());
+        assert!(vht(6, bw(4), GuardInterval::Long, 3).is_err());
+        assert!(vht(9, bw(4), GuardInterval::Long, 6).is_err());
+        assert!(vht(9, bw(11), GuardInterval::Long, 3).is_err());
+    }
+
+    #[test]
+    fn legacy_tables() {
+        use crate::field::ext::{ht_rate, vht_rate};
+
+        // The field rates keep the rounded values of the tables
+        assert_eq!(ht_rate(1, bw(0), GuardInterval::Short).unwrap(), 14.4);
+        assert_eq!(ht(1, bw(0), GuardInterval::Short).unwrap().mbps(), 14.444);
+        assert_eq!(vht_rate(9, bw(4), GuardInterval::Short, 1).unwrap(), 433.3);
+        assert!(vht_rate(9, bw(0), GuardInterval::Long, 1).is_err());
+    }
+
+    #[test]
+    fn he_and_eht_rates() {
+        let rate = he(11, RUSize::Tones996, HEGuardInterval::Ns800, 1, false).unwrap();
+        assert_eq!(rate.kbps, 600_490);
+        assert_eq!(rate.name(), "1024-QAM 5/6");
+
+        let dcm = he(1, RUSize::Tones242, HEGuardInterval::Ns800, 2, true).unwrap();
+        assert_eq!(dcm.kbps, 17_206);
+        assert!(he(2, RUSize::Tones242, HEGuardInterval::Ns800, 1, true).is_err());
+        assert!(he(0, RUSize::Tones4x996, HEGuardInterval::Ns800, 1, false).is_err());
+
+        let rate = he(0, RUSize::Tones26, HEGuardInterval::Ns3200, 1, false).unwrap();
+        assert_eq!(rate.kbps, 750);
+
+        let rate = eht(13, RUSize::Tones4x996, HEGuardInterval::Ns800, 1).unwrap();
+        assert_eq!(rate.kbps, 2_882_353);
+        assert_eq!(rate.name(), "4096-QAM 5/6");
+        assert!(eht(14, RUSize::Tones4x996, HEGuardInterval::Ns800, 1).is_err());
+    }
+}
@@ -0,0 +1,514 @@
+//! Filter expressions over the parsed fields of a Radiotap capture.
+//!
+//! A filter compares fields, named by their path in the
+//! [Radiotap](../struct.Radiotap.html) struct, to numbers or booleans, and
+//! combines the comparisons with `&&`, `||`, `!`, and parentheses. A field on
+//! its own tests that it is present, or for a flag that it is set. Any
+//! comparison with a field that is not present is false.
+//!
+//! ```
+//! use radiotap::{filter::Filter, Radiotap};
+//!
+//! let filter = Filter::new("channel.freq == 5180 && antenna_signal > -60").unwrap();
+//! assert!(!filter.matches(&Radiotap::default()));
+//! ```
+//!
+//! The fields and their members are those of the [schema](../schema/index.html).
+//! Single value fields such as `antenna_signal` can also be written with their
+//! member, as `antenna_signal.value`. Bandwidths such as `vht.bw` are compared
+//! in MHz, VHT users are indexed as `vht.users[0]`, and enum members are
+//! compared to the name of a variant, as in `vht.gi == Short`.
+
+use crate::{
+    schema::{self, Member, Type},
+    Error, Radiotap, Result,
+};
+
+/// A compiled filter expression.
+pub struct Filter {
+    node: Node,
+}
+
+impl Filter {
+    /// Returns the compiled filter expression.
+    pub fn new(expression: &str) -> Result<Filter> {
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            position: 0,
+            depth: 0,
+        };
+        let node = parser.or()?;
+        match parser.next() {
+            None => Ok(Filter { node }),
+            Some(token) => Err(invalid(format!("unexpected {}", token))),
+        }
+    }
+
+    /// Returns whether the Radiotap capture matches the filter.
+    pub fn matches(&self, radiotap: &Radiotap) -> bool {
+        self.node.eval(radiotap)
+    }
+}
+
+impl std::fmt::Debug for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Filter").finish()
+    }
+}
+
+impl std::str::FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Filter> {
+        Filter::new(s)
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidFilter(message)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Literal {
+    Number(f64),
+    Bool(bool),
+    Name(String),
+}
+
+enum Field {
+    Member(&'static Member),
+    /// A field with members, which can only be tested for presence.
+    Struct(fn(&Radiotap) -> bool),
+}
+
+/// Returns the value of a numeric member as an `f64`.
+fn number(value: schema::Value) -> Option<f64> {
+    match value {
+        schema::Value::UInt(v) => Some(v as f64),
+        schema::Value::Int(v) => Some(v as f64),
+        schema::Value::Float(v) => Some(f64::from(v)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn apply<T: PartialOrd>(self, a: T, b: T) -> bool {
+        match self {
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+        }
+    }
+}
+
+/// The deepest nesting of `!` and parentheses a filter may have, so that
+/// parsing and evaluating untrusted filters cannot overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+enum Node {
+    // Chains of the same operator are kept flat
+    And(Vec<Node>),
+    Or(Vec<Node>),
+    Not(Box<Node>),
+    Compare(&'static Member, Op, Literal),
+    Test(Field),
+}
+
+impl Node {
+    fn eval(&self, radiotap: &Radiotap) -> bool {
+        match self {
+            Node::And(nodes) => nodes.iter().all(|node| node.eval(radiotap)),
+            Node::Or(nodes) => nodes.iter().any(|node| node.eval(radiotap)),
+            Node::Not(a) => !a.eval(radiotap),
+            Node::Compare(member, op, literal) => match (member.get(radiotap), literal) {
+                (Some(schema::Value::Bool(a)), Literal::Bool(b)) => op.apply(a, *b),
+                (Some(schema::Value::Str(a)), Literal::Name(b)) => op.apply(a, b.as_str()),
+                (Some(a), Literal::Number(b)) => matches!(number(a), Some(a) if op.apply(a, *b)),
+                _ => false,
+            },
+            Node::Test(Field::Member(member)) => !matches!(
+                member.get(radiotap),
+                None | Some(schema::Value::Bool(false))
+            ),
+            Node::Test(Field::Struct(present)) => present(radiotap),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Dot,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Op(Op),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "`{}`", ident),
+            Token::Number(number) => write!(f, "`{}`", number),
+            Token::Dot => f.write_str("`.`"),
+            Token::LBracket => f.write_str("`[`"),
+            Token::RBracket => f.write_str("`]`"),
+            Token::LParen => f.write_str("`(`"),
+            Token::RParen => f.write_str("`)`"),
+            Token::And => f.write_str("`&&`"),
+            Token::Or => f.write_str("`||`"),
+            Token::Not => f.write_str("`!`"),
+            Token::Op(op) => write!(
+                f,
+                "`{}`",
+                match op {
+                    Op::Eq => "==",
+                    Op::Ne => "!=",
+                    Op::Lt => "<",
+                    Op::Le => "<=",
+                    Op::Gt => ">",
+                    Op::Ge => ">=",
+                }
+            ),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let token = match (c, next) {
+            (c, _) if c.is_whitespace() => continue,
+            ('.', _) => Token::Dot,
+            ('[', _) => Token::LBracket,
+            (']', _) => Token::RBracket,
+            ('(', _) => Token::LParen,
+            (')', _) => Token::RParen,
+            ('&', Some('&'))
+            | ('|', Some('|'))
+            | ('=', Some('='))
+            | ('!', Some('='))
+            | ('<', Some('='))
+            | ('>', Some('=')) => {
+                chars.next();
+                match c {
+                    '&' => Token::And,
+                    '|' => Token::Or,
+                    '=' => Token::Op(Op::Eq),
+                    '!' => Token::Op(Op::Ne),
+                    '<' => Token::Op(Op::Le),
+                    _ => Token::Op(Op::Ge),
+                }
+            }
+            ('!', _) => Token::Not,
+            ('<', _) => Token::Op(Op::Lt),
+            ('>', _) => Token::Op(Op::Gt),
+            (c, _)
+                if c.is_ascii_digit()
+                    || (c == '-' && matches!(next, Some(n) if n.is_ascii_digit())) =>
+            {
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_digit() || c == '.') {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                let number = &input[start..end];
+                Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid number `{}`", number)))?,
+                )
+            }
+            (c, _) if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                Token::Ident(input[start..end].to_string())
+            }
+            (c, _) => return Err(invalid(format!("unexpected character `{}`", c))),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Node> {
+        let mut nodes = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            nodes.push(self.and()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.remove(0),
+            _ => Node::Or(nodes),
+        })
+    }
+
+    fn and(&mut self) -> Result<Node> {
+        let mut nodes = vec![self.unary()?];
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            nodes.push(self.unary()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.remove(0),
+            _ => Node::And(nodes),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        match self.next() {
+            Some(Token::Not) => {
+                self.enter()?;
+                let node = Node::Not(Box::new(self.unary()?));
+                self.depth -= 1;
+                Ok(node)
+            }
+            Some(Token::LParen) => {
+                self.enter()?;
+                let node = self.or()?;
+                self.depth -= 1;
+                match self.next() {
+                    Some(Token::RParen) => Ok(node),
+                    _ => Err(invalid("expected `)`".to_string())),
+                }
+            }
+            Some(Token::Ident(ident)) => self.comparison(ident),
+            Some(token) => Err(invalid(format!("unexpected {}", token))),
+            None => Err(invalid("unexpected end of filter".to_string())),
+        }
+    }
+
+    fn enter(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(invalid(format!(
+                "nested more than {} levels deep",
+                MAX_DEPTH
+            )));
+        }
+        Ok(())
+    }
+
+    fn comparison(&mut self, first: String) -> Result<Node> {
+        let mut path = first;
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.next();
+                    match self.next() {
+                        Some(Token::Ident(ident)) => {
+                            path.push('.');
+                            path.push_str(&ident);
+                        }
+                        _ => return Err(invalid(format!("expected a member after `{}.`", path))),
+                    }
+                }
+                Some(Token::LBracket) => {
+                    self.next();
+                    match (self.next(), self.next()) {
+                        (Some(Token::Number(n)), Some(Token::RBracket))
+                            if n >= 0.0 && n.fract() == 0.0 =>
+                        {
+                            path.push_str(&format!("[{}]", n));
+                        }
+                        _ => return Err(invalid(format!("expected an index after `{}[`", path))),
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let field = resolve(&path).ok_or_else(|| invalid(format!("unknown field `{}`", path)))?;
+
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return Ok(Node::Test(field)),
+        };
+        self.next();
+
+        let member = match field {
+            Field::Member(member) => member,
+            Field::Struct(_) => return Err(invalid(format!("`{}` has members", path))),
+        };
+        let equality = matches!(op, Op::Eq | Op::Ne);
+
+        let literal = match (member.ty(), self.next()) {
+            (Type::Bool, Some(Token::Ident(ref b))) if equality => match b.as_str() {
+                "true" => Literal::Bool(true),
+                "false" => Literal::Bool(false),
+                _ => return Err(invalid(format!("expected a boolean, found `{}`", b))),
+            },
+            (Type::Bool, _) => {
+                return Err(invalid(format!(
+                    "`{}` can only be compared with `==` or `!=` to a boolean",
+                    path
+                )))
+            }
+            (Type::Str, Some(Token::Ident(name))) if equality => Literal::Name(name),
+            (Type::Str, _) => {
+                return Err(invalid(format!(
+                    "`{}` can only be compared with `==` or `!=` to a name",
+                    path
+                )))
+            }
+            // Compared at the precision the value is stored in
+            (Type::F32, Some(Token::Number(n))) => Literal::Number(f64::from(n as f32)),
+            (_, Some(Token::Number(n))) => Literal::Number(n),
+            (_, token) => {
+                return Err(invalid(format!(
+                    "expected a number after `{}`, found {}",
+                    path,
+                    token.map_or("the end".to_string(), |t| t.to_string())
+                )))
+            }
+        };
+
+        Ok(Node::Compare(member, op, literal))
+    }
+}
+
+/// Returns the member or field with members at the path.
+fn resolve(path: &str) -> Option<Field> {
+    let member = schema::member(path)
+        // Single value fields can also be written with their member
+        .or_else(|| {
+            path.strip_suffix(".value")
+                .filter(|field| !field.contains('.'))
+                .and_then(schema::member)
+        })
+        // Bandwidths are compared in MHz
+        .or_else(|| schema::member(&format!("{}.bandwidth", path)));
+    match member {
+        Some(member) => Some(Field::Member(member)),
+        None => schema::presence(path).map(Field::Struct),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::vht_radiotap;
+
+    fn matches(expression: &str) -> bool {
+        Filter::new(expression).unwrap().matches(&vht_radiotap())
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(matches(
+            "channel.freq == 5500 && antenna_signal > -60 && vht.users[0].nss >= 3"
+        ));
+        assert!(matches("antenna_signal.value <= -43 && vht.bw == 80"));
+        assert!(!matches("antenna_signal < -43 || channel.freq != 5500"));
+        assert!(matches(
+            "!(vht.users[0].index > 8) && vht.users[0].datarate == 877.5"
+        ));
+        assert!(matches("channel.flags.ghz5 && channel.flags.ofdm == true"));
+    }
+
+    #[test]
+    fn missing_fields() {
+        assert!(!matches("mcs.index == 7"));
+        assert!(!matches("mcs"));
+        assert!(matches("!mcs && vht && vht.users[0] && !vht.users[1]"));
+        assert!(!matches("vht.users[1].nss != 2"));
+    }
+
+    #[test]
+    fn invalid() {
+        for &expression in [
+            "",
+            "channel.freq ==",
+            "channel.frequency == 5180",
+            "channel == 1",
+            "channel.flags.ghz5 > 1",
+            "(vht",
+            "vht.users[4]",
+            "antenna_signal > -60 &&",
+            "antenna_signal # 1",
+        ]
+        .iter()
+        {
+            match Filter::new(expression) {
+                Err(Error::InvalidFilter(_)) => {}
+                other => panic!("{:?} did not fail: {:?}", expression, other),
+            }
+        }
+    }
+
+    #[test]
+    fn schema_members() {
+        for member in schema::members() {
+            assert!(Filter::new(member.path()).is_ok(), "{}", member.path());
+        }
+        assert!(matches(
+            "vht.users[0].fec == BCC && vht.gi == Long && vht.bw.bandwidth == 80"
+        ));
+        assert!(!matches("mcs.gi || vht.sgi_nsym_da || timestamp.position"));
+        assert!(Filter::new("vht.gi > Short").is_err());
+    }
+
+    #[test]
+    fn nesting() {
+        assert!(matches(&format!("{}vht", "!".repeat(MAX_DEPTH))));
+        assert!(matches(&["vht"; 100_000].join(" && ")));
+
+        for expression in [
+            format!("{}vht", "!".repeat(100_000)),
+            format!("{}vht{}", "(".repeat(100_000), ")".repeat(100_000)),
+        ] {
+            match Filter::new(&expression) {
+                Err(Error::InvalidFilter(_)) => {}
+                other => panic!("deep nesting did not fail: {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Consistency checks between the Radiotap header and the 802.11 frame.
+
+use crate::{
+    channel::{channel_to_freq, Band},
+    ieee80211::mgmt::element::*,
+    Radiotap, Result,
+};
+
+/// The information element that advertised the primary channel.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ChannelSource {
+    /// The 6 GHz operation information of the HE Operation element.
+    HeOperation,
+    /// The HT Operation element.
+    HtOperation,
+    /// The DS Parameter Set element.
+    DsParameterSet,
+}
+
+/// A disagreement between the channel reported by the driver and the channel
+/// advertised by the frame.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ChannelMismatch {
+    /// The frequency in MHz reported in the Radiotap header.
+    pub reported: u16,
+    /// The frequency in MHz of the advertised primary channel.
+    pub advertised: u16,
+    /// The advertised primary channel number.
+    pub channel: u8,
+    /// The element that advertised the primary channel.
+    pub source: ChannelSource,
+}
+
+/// The result of checking the reported channel of a frame.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ChannelCheck {
+    /// The reported channel matches the advertised primary channel.
+    Match(ChannelSource),
+    /// The reported channel differs from the advertised primary channel.
+    Mismatch(ChannelMismatch),
+    /// The Radiotap header has no channel, or the frame does not advertise
+    /// its primary channel.
+    Unknown,
+}
+
+/// Compares the channel in the Radiotap header against the primary channel
+/// advertised in the information elements of a beacon or probe response.
+///
+/// The XChannel frequency is preferred over the Channel frequency. The
+/// primary channel is taken from the first of the HE Operation 6 GHz
+/// information, the HT Operation, and the DS Parameter Set elements that is
+/// present. The VHT Operation element and the HE Operation element outside of
+/// 6 GHz only describe the channel width and center, so they are not used.
+pub fn check_channel(radiotap: &Radiotap, elements: &ElementIterator) -> Result<ChannelCheck> {
+    let reported = match (radiotap.xchannel, radiotap.channel) {
+        (Some(xchannel), _) => xchannel.freq,
+        (None, Some(channel)) => channel.freq,
+        (None, None) => return Ok(ChannelCheck::Unknown),
+    };
+
+    let (channel, six_ghz, source) = if let Some(six_ghz) = elements
+        .get::<HeOperation>(ElementKind::HeOperation)
+        .transpose()?
+        .and_then(|he| he.six_ghz_operation)
+    {
+        (six_ghz.primary_channel, true, ChannelSource::HeOperation)
+    } else if let Some(ht) = elements
+        .get::<HtOperation>(ElementKind::HtOperation)
+        .transpose()?
+    {
+        (ht.primary_channel, false, ChannelSource::HtOperation)
+    } else if let Some(ds) = elements
+        .get::<DsParameterSet>(ElementKind::DsParameterSet)
+        .transpose()?
+    {
+        (ds.channel, false, ChannelSource::DsParameterSet)
+    } else {
+        return Ok(ChannelCheck::Unknown);
+    };
+
+    let band = match (six_ghz, channel) {
+        (true, _) => Band::Ghz6,
+        (false, 1..=14) => Band::Ghz2,
+        (false, _) => Band::Ghz5,
+    };
+    let advertised = match channel_to_freq(channel, band) {
+        Some(freq) => freq as u16,
+        None => return Ok(ChannelCheck::Unknown),
+    };
+
+    if advertised == reported {
+        Ok(ChannelCheck::Match(source))
+    } else {
+        Ok(ChannelCheck::Mismatch(ChannelMismatch {
+            reported,
+            advertised,
+            channel,
+            source,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::{ext::ChannelFlags, Channel};
+
+    fn radiotap(freq: u16) -> Radiotap {
+        Radiotap {
+            channel: Some(Channel {
+                freq,
+                flags: ChannelFlags {
+                    turbo: false,
+                    cck: false,
+                    ofdm: true,
+                    ghz2: freq < 3000,
+                    ghz5: freq > 5000,
+                    passive: false,
+                    dynamic: false,
+                    gfsk: false,
+                },
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ds_parameter_set() {
+        let body = [3, 1, 6];
+        let elements = ElementIterator::from_bytes(&body);
+
+        assert_eq!(
+            check_channel(&radiotap(2437), &elements).unwrap(),
+            ChannelCheck::Match(ChannelSource::DsParameterSet)
+        );
+        assert_eq!(
+            check_channel(&radiotap(2412), &elements).unwrap(),
+            ChannelCheck::Mismatch(ChannelMismatch {
+                reported: 2412,
+                advertised: 2437,
+                channel: 6,
+                source: ChannelSource::DsParameterSet,
+            })
+        );
+    }
+
+    #[test]
+    fn ht_operation_preferred() {
+        let mut body = vec![3, 1, 1, 61, 22, 36, 0x05];
+        body.extend_from_slice(&[0; 20]);
+        let elements = ElementIterator::from_bytes(&body);
+
+        assert_eq!(
+            check_channel(&radiotap(5180), &elements).unwrap(),
+            ChannelCheck::Match(ChannelSource::HtOperation)
+        );
+    }
+
+    #[test]
+    fn he_operation_6ghz() {
+        let body = [
+            255, 12, 36, 0x00, 0x00, 0x02, 0x05, 0xfc, 0xff, 37, 0x02, 39, 0, 6,
+        ];
+        let elements = ElementIterator::from_bytes(&body);
+
+        match check_channel(&radiotap(5955), &elements).unwrap() {
+            ChannelCheck::Mismatch(mismatch) => {
+                assert_eq!(mismatch.advertised, 6135);
+                assert_eq!(mismatch.source, ChannelSource::HeOperation);
+            }
+            c => panic!("Check not Mismatch: {:?}", c),
+        }
+    }
+
+    #[test]
+    fn unknown() {
+        let elements = ElementIterator::from_bytes(&[0, 0]);
+        assert_eq!(
+            check_channel(&radiotap(2412), &elements).unwrap(),
+            ChannelCheck::Unknown
+        );
+        assert_eq!(
+            check_channel(
+                &Radiotap::default(),
+                &ElementIterator::from_bytes(&[3, 1, 6])
+            )
+            .unwrap(),
+            ChannelCheck::Unknown
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Parser for the body of 802.11 block ack frames.
+
+use bitops::BitOps;
+use byteorder::{ReadBytesExt, LE};
+use std::io::Cursor;
+
+use crate::{
+    ieee80211::{Frame, FrameType},
+    Error, Result,
+};
+
+/// The variant of a block ack frame.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BlockAckVariant {
+    /// A 128 byte bitmap with 16 fragment bits for each MSDU.
+    Basic,
+    /// A bitmap with a single bit for each MSDU.
+    Compressed,
+    /// A compressed bitmap used by DMG STAs.
+    ExtendedCompressed,
+    /// A bitmap for each of several TIDs.
+    MultiTid,
+    /// A variant this crate does not parse.
+    Other(u8),
+}
+
+/// The body of a block ack frame.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BlockAck {
+    /// The block ack variant.
+    pub variant: BlockAckVariant,
+    /// Whether the block ack requires no acknowledgement.
+    pub no_ack: bool,
+    /// The TID the block ack applies to, or the number of TIDs minus 1 for
+    /// multi-TID block acks.
+    pub tid: u8,
+    /// The sequence number of the first MSDU in the bitmap.
+    pub starting_sequence: Option<u16>,
+    /// The block ack bitmap, or the remaining BA information for variants
+    /// this crate does not parse.
+    pub bitmap: Vec<u8>,
+}
+
+impl BlockAck {
+    /// Returns the parsed body of a block ack control frame.
+    pub fn parse(frame: &Frame) -> Result<BlockAck> {
+        let fc = frame.header.frame_control;
+        if fc.frame_type != FrameType::Control || fc.subtype != 9 {
+            return Err(Error::InvalidFormat);
+        }
+        BlockAck::from_bytes(frame.body)
+    }
+
+    /// Returns the parsed block ack from the BA control and BA information
+    /// fields.
+    pub fn from_bytes(input: &[u8]) -> Result<BlockAck> {
+        let mut cursor = Cursor::new(input);
+        let control = cursor.read_u16::<LE>()?;
+
+        let variant = match control.bits_as_int(1, 4) {
+            0 => BlockAckVariant::Basic,
+            1 => BlockAckVariant::ExtendedCompressed,
+            2 => BlockAckVariant::Compressed,
+            3 => BlockAckVariant::MultiTid,
+            other => BlockAckVariant::Other(other as u8),
+        };
+
+        let starting_sequence = match variant {
+            BlockAckVariant::Basic
+            | BlockAckVariant::Compressed
+            | BlockAckVariant::ExtendedCompressed => Some(cursor.read_u16::<LE>()? >> 4),
+            _ => None,
+        };
+
+        Ok(BlockAck {
+            variant,
+            no_ack: control.is_flag_set(0x0001),
+            tid: control.bits_as_int(12, 4) as u8,
+            starting_sequence,
+            bitmap: input[cursor.position() as usize..].to_vec(),
+        })
+    }
+
+    /// Whether the MSDU with the given sequence number was acknowledged.
+    /// Always false for variants without a single bitmap.
+    pub fn acknowledged(&self, sequence: u16) -> bool {
+        let start = match self.starting_sequence {
+            Some(start) => start,
+            None => return false,
+        };
+
+        // Sequence numbers are modulo 4096
+        let offset = (sequence.wrapping_sub(start) & 0x0fff) as usize;
+
+        // The basic bitmap has two bytes per MSDU, one bit for each fragment
+        let (index, mask) = match self.variant {
+            BlockAckVariant::Basic => (offset * 2, 0x01),
+            _ => (offset / 8, 1 << (offset % 8)),
+        };
+
+        matches!(self.bitmap.get(index), Some(byte) if byte & mask != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compressed() {
+        let body = [
+            0x04, 0x50, // compressed, TID 5
+            0x00, 0x64, // starting sequence 1600
+            0x0b, 0, 0, 0, 0, 0, 0, 0x80,
+        ];
+        let ba = BlockAck::from_bytes(&body).unwrap();
+
+        assert_eq!(ba.variant, BlockAckVariant::Compressed);
+        assert_eq!(ba.tid, 5);
+        assert_eq!(ba.starting_sequence, Some(1600));
+        assert!(ba.acknowledged(1600));
+        assert!(ba.acknowledged(1601));
+        assert!(!ba.acknowledged(1602));
+        assert!(ba.acknowledged(1663));
+        assert!(!ba.acknowledged(1664));
+    }
+
+    #[test]
+    fn sequence_wraps() {
+        let body = [0x04, 0x00, 0xf0, 0xff, 0x03, 0, 0, 0, 0, 0, 0, 0];
+        let ba = BlockAck::from_bytes(&body).unwrap();
+
+        assert_eq!(ba.starting_sequence, Some(4095));
+        assert!(ba.acknowledged(4095));
+        assert!(ba.acknowledged(0));
+    }
+}
@@ -0,0 +1,179 @@
+//! Verification of the 802.11 frame check sequence.
+
+use crate::{ieee80211::MacHeader, Radiotap};
+
+/// The result of checking the FCS of a captured frame.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FcsStatus {
+    /// The FCS matches the frame contents.
+    Valid,
+    /// The FCS does not match the frame contents.
+    Invalid,
+    /// The capture does not include the FCS.
+    Absent,
+    /// The capture does not include the FCS, but the driver reported that the
+    /// FCS check failed.
+    DriverFlaggedBad,
+}
+
+impl FcsStatus {
+    /// Whether the computed status contradicts the `bad_fcs` flag reported by
+    /// the driver.
+    pub fn contradicts_driver(self, radiotap: &Radiotap) -> bool {
+        let bad_fcs = matches!(radiotap.flags, Some(flags) if flags.bad_fcs);
+        match self {
+            FcsStatus::Valid => bad_fcs,
+            FcsStatus::Invalid => !bad_fcs,
+            FcsStatus::Absent | FcsStatus::DriverFlaggedBad => false,
+        }
+    }
+}
+
+/// Verifies the FCS of the 802.11 frame following the Radiotap header.
+///
+/// The CRC-32 is computed over the MPDU, excluding any padding the driver
+/// inserted after the MAC header, and compared against the trailing four
+/// bytes.
+pub fn verify_fcs(radiotap: &Radiotap, frame: &[u8]) -> FcsStatus {
+    let flags = match radiotap.flags {
+        Some(flags) => flags,
+        None => return FcsStatus::Absent,
+    };
+
+    if !flags.fcs {
+        return if flags.bad_fcs {
+            FcsStatus::DriverFlaggedBad
+        } else {
+            FcsStatus::Absent
+        };
+    }
+
+    if frame.len() < 4 {
+        return FcsStatus::Invalid;
+    }
+    let (mpdu, fcs) = frame.split_at(frame.len() - 4);
+    let fcs = u32::from_le_bytes([fcs[0], fcs[1], fcs[2], fcs[3]]);
+
+    let crc = match MacHeader::from_bytes(mpdu) {
+        Ok(header) if flags.data_pad => {
+            let start = ((header.length + 3) & !3).min(mpdu.len());
+            crc32_update(crc32_update(!0, &mpdu[..header.length]), &mpdu[start..])
+        }
+        _ => crc32_update(!0, mpdu),
+    };
+
+    if !crc == fcs {
+        FcsStatus::Valid
+    } else {
+        FcsStatus::Invalid
+    }
+}
+
+/// Returns the IEEE 802.3 CRC-32 of the input, as used by the 802.11 FCS.
+pub fn crc32(input: &[u8]) -> u32 {
+    !crc32_update(!0, input)
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32_update(crc: u32, input: &[u8]) -> u32 {
+    input.iter().fold(crc, |crc, &byte| {
+        CRC32_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Flags;
+
+    fn radiotap(fcs: bool, data_pad: bool, bad_fcs: bool) -> Radiotap {
+        Radiotap {
+            flags: Some(Flags {
+                cfp: false,
+                preamble: false,
+                wep: false,
+                fragmentation: false,
+                fcs,
+                data_pad,
+                bad_fcs,
+                sgi: false,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn with_fcs(mpdu: &[u8]) -> Vec<u8> {
+        let mut frame = mpdu.to_vec();
+        frame.extend_from_slice(&crc32(mpdu).to_le_bytes());
+        frame
+    }
+
+    const ACK: [u8; 10] = [0xd4, 0x00, 0x00, 0x00, 1, 2, 3, 4, 5, 6];
+
+    #[test]
+    fn check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn valid_and_invalid() {
+        let mut frame = with_fcs(&ACK);
+        assert_eq!(
+            verify_fcs(&radiotap(true, false, false), &frame),
+            FcsStatus::Valid
+        );
+
+        frame[4] ^= 0xff;
+        let status = verify_fcs(&radiotap(true, false, false), &frame);
+        assert_eq!(status, FcsStatus::Invalid);
+        assert!(status.contradicts_driver(&radiotap(true, false, false)));
+    }
+
+    #[test]
+    fn padding_is_excluded() {
+        let mpdu = [
+            0x88, 0x02, 0x00, 0x00, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 0x10,
+            0x00, 0x05, 0x00, 0xaa, 0xbb,
+        ];
+        let mut frame = with_fcs(&mpdu);
+        frame.splice(26..26, [0, 0].iter().cloned());
+
+        assert_eq!(
+            verify_fcs(&radiotap(true, true, false), &frame),
+            FcsStatus::Valid
+        );
+    }
+
+    #[test]
+    fn absent() {
+        assert_eq!(
+            verify_fcs(&radiotap(false, false, false), &ACK),
+            FcsStatus::Absent
+        );
+        assert_eq!(
+            verify_fcs(&radiotap(false, false, true), &ACK),
+            FcsStatus::DriverFlaggedBad
+        );
+    }
+}
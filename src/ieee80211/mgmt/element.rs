@@ -0,0 +1,541 @@
+//! Information element definitions and parsers.
+
+use bitops::BitOps;
+use byteorder::{ReadBytesExt, LE};
+use std::io::{Cursor, Read};
+
+use crate::{Error, Result};
+
+type Oui = [u8; 3];
+
+/// The type of information element.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ElementKind {
+    Ssid,
+    SupportedRates,
+    DsParameterSet,
+    HtCapabilities,
+    Rsn,
+    ExtendedSupportedRates,
+    HtOperation,
+    VhtCapabilities,
+    VhtOperation,
+    VendorSpecific,
+    HeCapabilities,
+    HeOperation,
+    /// An element this crate does not parse, with its element ID.
+    Other(u8),
+    /// An extension element this crate does not parse, with its element ID
+    /// extension.
+    Extension(u8),
+}
+
+impl ElementKind {
+    /// Returns the kind for an element ID, and the element ID extension if
+    /// the element ID is 255.
+    pub fn new(id: u8, extension: Option<u8>) -> ElementKind {
+        match (id, extension) {
+            (0, _) => ElementKind::Ssid,
+            (1, _) => ElementKind::SupportedRates,
+            (3, _) => ElementKind::DsParameterSet,
+            (45, _) => ElementKind::HtCapabilities,
+            (48, _) => ElementKind::Rsn,
+            (50, _) => ElementKind::ExtendedSupportedRates,
+            (61, _) => ElementKind::HtOperation,
+            (191, _) => ElementKind::VhtCapabilities,
+            (192, _) => ElementKind::VhtOperation,
+            (221, _) => ElementKind::VendorSpecific,
+            (255, Some(35)) => ElementKind::HeCapabilities,
+            (255, Some(36)) => ElementKind::HeOperation,
+            (255, Some(ext)) => ElementKind::Extension(ext),
+            (id, _) => ElementKind::Other(id),
+        }
+    }
+}
+
+/// A parser for the information elements in a management frame body.
+#[derive(Clone, Copy, Debug)]
+pub struct ElementIterator<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ElementIterator<'a> {
+    pub fn from_bytes(input: &'a [u8]) -> ElementIterator<'a> {
+        ElementIterator { data: input }
+    }
+
+    /// Returns the first element of the given kind, parsed as `T`.
+    pub fn get<T>(&self, kind: ElementKind) -> Option<Result<T>>
+    where
+        T: Element,
+    {
+        self.into_iter().find_map(|element| match element {
+            Ok((k, data)) if k == kind => Some(T::from_bytes(data)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+    }
+}
+
+/// An iterator over information elements.
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub struct ElementIteratorIntoIter<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> IntoIterator for &ElementIterator<'a> {
+    type IntoIter = ElementIteratorIntoIter<'a>;
+    type Item = Result<(ElementKind, &'a [u8])>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ElementIteratorIntoIter { data: self.data }
+    }
+}
+
+impl<'a> IntoIterator for ElementIterator<'a> {
+    type IntoIter = ElementIteratorIntoIter<'a>;
+    type Item = Result<(ElementKind, &'a [u8])>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ElementIteratorIntoIter { data: self.data }
+    }
+}
+
+impl<'a> Iterator for ElementIteratorIntoIter<'a> {
+    type Item = Result<(ElementKind, &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        // The element claims to be longer than the remaining body
+        if self.data.len() < 2 || self.data.len() < 2 + self.data[1] as usize {
+            self.data = &[];
+            return Some(Err(Error::IncompleteError));
+        }
+
+        let id = self.data[0];
+        let (element, rest) = self.data.split_at(2 + self.data[1] as usize);
+        self.data = rest;
+
+        let mut data = &element[2..];
+        let mut extension = None;
+        if id == 255 && !data.is_empty() {
+            extension = Some(data[0]);
+            data = &data[1..];
+        }
+
+        Some(Ok((ElementKind::new(id, extension), data)))
+    }
+}
+
+pub trait Element {
+    fn from_bytes(input: &[u8]) -> Result<Self>
+    where
+        Self: Sized;
+}
+
+/// Parse any `Element` and return a `Result<T>`.
+pub fn from_bytes<T>(input: &[u8]) -> Result<T>
+where
+    T: Element,
+{
+    T::from_bytes(input)
+}
+
+/// The name of the network.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Ssid {
+    /// The SSID, usually but not necessarily UTF-8.
+    pub value: Vec<u8>,
+}
+
+impl Element for Ssid {
+    fn from_bytes(input: &[u8]) -> Result<Ssid> {
+        if input.len() > 32 {
+            return Err(Error::InvalidFormat);
+        }
+        Ok(Ssid {
+            value: input.to_vec(),
+        })
+    }
+}
+
+impl Ssid {
+    /// Whether this is a wildcard or hidden SSID.
+    pub fn is_hidden(&self) -> bool {
+        self.value.iter().all(|&b| b == 0)
+    }
+}
+
+/// A rate in the Supported Rates or Extended Supported Rates element.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SupportedRate {
+    /// The rate in Mbps.
+    pub rate: f32,
+    /// Whether the rate is in the basic rate set.
+    pub basic: bool,
+}
+
+/// The rates supported by the transmitter. Also used for the Extended
+/// Supported Rates element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SupportedRates {
+    pub rates: Vec<SupportedRate>,
+}
+
+impl Element for SupportedRates {
+    fn from_bytes(input: &[u8]) -> Result<SupportedRates> {
+        let rates = input
+            .iter()
+            .map(|&value| SupportedRate {
+                rate: f32::from(value & 0x7f) / 2.0,
+                basic: value.is_flag_set(0x80),
+            })
+            .collect();
+        Ok(SupportedRates { rates })
+    }
+}
+
+/// The channel of a DSSS, HR/DSSS, or ERP network.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DsParameterSet {
+    /// The current channel number.
+    pub channel: u8,
+}
+
+impl Element for DsParameterSet {
+    fn from_bytes(input: &[u8]) -> Result<DsParameterSet> {
+        let channel = Cursor::new(input).read_u8()?;
+        Ok(DsParameterSet { channel })
+    }
+}
+
+/// The HT capabilities of the transmitter.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HtCapabilities {
+    /// LDPC coded packets can be received.
+    pub ldpc: bool,
+    /// Both 20 MHz and 40 MHz operation is supported.
+    pub channel_width_40: bool,
+    /// Short guard interval is supported for 20 MHz packets.
+    pub short_gi_20: bool,
+    /// Short guard interval is supported for 40 MHz packets.
+    pub short_gi_40: bool,
+    /// The maximum A-MPDU length is 2^(13 + exponent) - 1 bytes.
+    pub max_ampdu_length_exponent: u8,
+    /// Bitmask of the supported receive MCS indices 0 - 76.
+    pub rx_mcs: [u8; 10],
+}
+
+impl Element for HtCapabilities {
+    fn from_bytes(input: &[u8]) -> Result<HtCapabilities> {
+        let mut cursor = Cursor::new(input);
+        let info = cursor.read_u16::<LE>()?;
+        let ampdu_params = cursor.read_u8()?;
+        let mut rx_mcs = [0; 10];
+        cursor.read_exact(&mut rx_mcs)?;
+        Ok(HtCapabilities {
+            ldpc: info.is_flag_set(0x0001),
+            channel_width_40: info.is_flag_set(0x0002),
+            short_gi_20: info.is_flag_set(0x0020),
+            short_gi_40: info.is_flag_set(0x0040),
+            max_ampdu_length_exponent: ampdu_params & 0x03,
+            rx_mcs,
+        })
+    }
+}
+
+/// The position of the secondary channel relative to the primary channel.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SecondaryChannelOffset {
+    None,
+    Above,
+    Below,
+}
+
+/// The operation of an HT network.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HtOperation {
+    /// The primary channel number.
+    pub primary_channel: u8,
+    /// The position of the secondary channel.
+    pub secondary_channel_offset: SecondaryChannelOffset,
+    /// Whether channel widths wider than 20 MHz may be used.
+    pub any_channel_width: bool,
+    /// Channel center frequency segment 2, used by VHT for 160 and 80+80 MHz
+    /// networks.
+    pub center_freq_seg2: u8,
+}
+
+impl Element for HtOperation {
+    fn from_bytes(input: &[u8]) -> Result<HtOperation> {
+        let mut cursor = Cursor::new(input);
+        let primary_channel = cursor.read_u8()?;
+        let info = cursor.read_u8()?;
+        let info2 = cursor.read_u16::<LE>()?;
+        Ok(HtOperation {
+            primary_channel,
+            secondary_channel_offset: match info & 0x03 {
+                1 => SecondaryChannelOffset::Above,
+                3 => SecondaryChannelOffset::Below,
+                _ => SecondaryChannelOffset::None,
+            },
+            any_channel_width: info.is_flag_set(0x04),
+            center_freq_seg2: info2.bits_as_int(5, 8) as u8,
+        })
+    }
+}
+
+/// The VHT capabilities of the transmitter.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct VhtCapabilities {
+    /// The supported channel width set, 0 for 80 MHz, 1 for 160 MHz, and 2
+    /// for 160 and 80+80 MHz.
+    pub supported_channel_width: u8,
+    /// LDPC coded packets can be received.
+    pub rx_ldpc: bool,
+    /// Short guard interval is supported for 80 MHz packets.
+    pub short_gi_80: bool,
+    /// Short guard interval is supported for 160 and 80+80 MHz packets.
+    pub short_gi_160: bool,
+    /// The maximum MCS supported for each number of receive spatial streams.
+    pub rx_mcs_map: u16,
+    /// The maximum MCS supported for each number of transmit spatial streams.
+    pub tx_mcs_map: u16,
+}
+
+impl Element for VhtCapabilities {
+    fn from_bytes(input: &[u8]) -> Result<VhtCapabilities> {
+        let mut cursor = Cursor::new(input);
+        let info = cursor.read_u32::<LE>()?;
+        let rx_mcs_map = cursor.read_u16::<LE>()?;
+        cursor.read_u16::<LE>()?; // Account for the RX highest data rate
+        let tx_mcs_map = cursor.read_u16::<LE>()?;
+        Ok(VhtCapabilities {
+            supported_channel_width: info.bits_as_int(2, 2) as u8,
+            rx_ldpc: info.is_flag_set(0x0000_0010),
+            short_gi_80: info.is_flag_set(0x0000_0020),
+            short_gi_160: info.is_flag_set(0x0000_0040),
+            rx_mcs_map,
+            tx_mcs_map,
+        })
+    }
+}
+
+/// The operation of a VHT network.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct VhtOperation {
+    /// The channel width, 0 for 20 or 40 MHz, 1 for 80, 160 or 80+80 MHz, 2
+    /// for 160 MHz (deprecated), and 3 for 80+80 MHz (deprecated).
+    pub channel_width: u8,
+    /// Channel center frequency segment 0.
+    pub center_freq_seg0: u8,
+    /// Channel center frequency segment 1.
+    pub center_freq_seg1: u8,
+    /// The basic MCS for each number of spatial streams.
+    pub basic_mcs_map: u16,
+}
+
+impl Element for VhtOperation {
+    fn from_bytes(input: &[u8]) -> Result<VhtOperation> {
+        let mut cursor = Cursor::new(input);
+        Ok(VhtOperation {
+            channel_width: cursor.read_u8()?,
+            center_freq_seg0: cursor.read_u8()?,
+            center_freq_seg1: cursor.read_u8()?,
+            basic_mcs_map: cursor.read_u16::<LE>()?,
+        })
+    }
+}
+
+/// The HE capabilities of the transmitter.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HeCapabilities {
+    /// The HE MAC capabilities information.
+    pub mac: [u8; 6],
+    /// The HE PHY capabilities information.
+    pub phy: [u8; 11],
+    /// The supported channel width set from the PHY capabilities.
+    pub channel_width_set: u8,
+}
+
+impl Element for HeCapabilities {
+    fn from_bytes(input: &[u8]) -> Result<HeCapabilities> {
+        let mut cursor = Cursor::new(input);
+        let mut mac = [0; 6];
+        cursor.read_exact(&mut mac)?;
+        let mut phy = [0; 11];
+        cursor.read_exact(&mut phy)?;
+        Ok(HeCapabilities {
+            mac,
+            phy,
+            channel_width_set: phy[0] >> 1,
+        })
+    }
+}
+
+/// The operation of an HE network in the 6 GHz band.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SixGhzOperation {
+    /// The primary channel number.
+    pub primary_channel: u8,
+    /// The channel width, 0 for 20 MHz, 1 for 40 MHz, 2 for 80 MHz, and 3
+    /// for 160 or 80+80 MHz.
+    pub channel_width: u8,
+    /// Channel center frequency segment 0.
+    pub center_freq_seg0: u8,
+    /// Channel center frequency segment 1.
+    pub center_freq_seg1: u8,
+    /// The minimum rate in units of 1 Mbps.
+    pub min_rate: u8,
+}
+
+/// The operation of an HE network.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HeOperation {
+    /// The BSS color.
+    pub bss_color: u8,
+    /// The basic HE-MCS for each number of spatial streams.
+    pub basic_mcs_map: u16,
+    /// The VHT operation information, present when VHT is not used to
+    /// describe the channel.
+    pub vht_operation: Option<VhtOperation>,
+    /// The 6 GHz operation information.
+    pub six_ghz_operation: Option<SixGhzOperation>,
+}
+
+impl Element for HeOperation {
+    fn from_bytes(input: &[u8]) -> Result<HeOperation> {
+        let mut cursor = Cursor::new(input);
+        let params = cursor.read_u24::<LE>()?;
+        let bss_color = cursor.read_u8()? & 0x3f;
+        let basic_mcs_map = cursor.read_u16::<LE>()?;
+
+        let mut operation = HeOperation {
+            bss_color,
+            basic_mcs_map,
+            vht_operation: None,
+            six_ghz_operation: None,
+        };
+
+        if params.is_flag_set(0x00_4000) {
+            operation.vht_operation = Some(VhtOperation {
+                channel_width: cursor.read_u8()?,
+                center_freq_seg0: cursor.read_u8()?,
+                center_freq_seg1: cursor.read_u8()?,
+                basic_mcs_map: 0,
+            });
+        }
+
+        if params.is_flag_set(0x00_8000) {
+            cursor.read_u8()?; // Account for the max co-hosted BSSID indicator
+        }
+
+        if params.is_flag_set(0x02_0000) {
+            let primary_channel = cursor.read_u8()?;
+            let control = cursor.read_u8()?;
+            operation.six_ghz_operation = Some(SixGhzOperation {
+                primary_channel,
+                channel_width: control & 0x03,
+                center_freq_seg0: cursor.read_u8()?,
+                center_freq_seg1: cursor.read_u8()?,
+                min_rate: cursor.read_u8()?,
+            });
+        }
+
+        Ok(operation)
+    }
+}
+
+/// A cipher or AKM suite selector.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Suite {
+    pub oui: Oui,
+    pub suite_type: u8,
+}
+
+impl Suite {
+    fn read(cursor: &mut Cursor<&[u8]>) -> Result<Suite> {
+        let mut oui = [0; 3];
+        cursor.read_exact(&mut oui)?;
+        let suite_type = cursor.read_u8()?;
+        Ok(Suite { oui, suite_type })
+    }
+
+    fn read_list(cursor: &mut Cursor<&[u8]>) -> Result<Vec<Suite>> {
+        let count = cursor.read_u16::<LE>()?;
+        (0..count).map(|_| Suite::read(cursor)).collect()
+    }
+}
+
+/// The robust security network parameters. All fields after the version are
+/// optional and default to CCMP-128 with 802.1X when absent.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Rsn {
+    /// The RSN version, currently always 1.
+    pub version: u16,
+    /// The cipher suite for group addressed frames.
+    pub group_cipher: Option<Suite>,
+    /// The cipher suites for individually addressed frames.
+    pub pairwise_ciphers: Vec<Suite>,
+    /// The authentication and key management suites.
+    pub akm_suites: Vec<Suite>,
+    /// The RSN capabilities.
+    pub capabilities: Option<u16>,
+}
+
+impl Element for Rsn {
+    fn from_bytes(input: &[u8]) -> Result<Rsn> {
+        let mut cursor = Cursor::new(input);
+        let mut rsn = Rsn {
+            version: cursor.read_u16::<LE>()?,
+            group_cipher: None,
+            pairwise_ciphers: Vec::new(),
+            akm_suites: Vec::new(),
+            capabilities: None,
+        };
+
+        let remaining = |cursor: &Cursor<&[u8]>| input.len() as u64 > cursor.position();
+
+        if remaining(&cursor) {
+            rsn.group_cipher = Some(Suite::read(&mut cursor)?);
+        }
+        if remaining(&cursor) {
+            rsn.pairwise_ciphers = Suite::read_list(&mut cursor)?;
+        }
+        if remaining(&cursor) {
+            rsn.akm_suites = Suite::read_list(&mut cursor)?;
+        }
+        if remaining(&cursor) {
+            rsn.capabilities = Some(cursor.read_u16::<LE>()?);
+        }
+
+        Ok(rsn)
+    }
+}
+
+/// A vendor specific element.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct VendorSpecific {
+    /// The organization identifier of the vendor.
+    pub oui: Oui,
+    /// The vendor specific content, including any vendor defined type.
+    pub data: Vec<u8>,
+}
+
+impl Element for VendorSpecific {
+    fn from_bytes(input: &[u8]) -> Result<VendorSpecific> {
+        if input.len() < 3 {
+            return Err(Error::IncompleteError);
+        }
+        let mut oui = [0; 3];
+        oui.copy_from_slice(&input[..3]);
+        Ok(VendorSpecific {
+            oui,
+            data: input[3..].to_vec(),
+        })
+    }
+}
@@ -0,0 +1,339 @@
+//! Parsers for 802.11 management frame bodies and their information elements.
+//!
+//! ```
+//! use radiotap::{
+//!     ieee80211::{
+//!         mgmt::{element::*, ManagementFrame},
+//!         Frame,
+//!     },
+//!     Radiotap,
+//! };
+//!
+//! let capture = [
+//!     0, 0, 8, 0, 0, 0, 0, 0, 128, 0, 0, 0, 255, 255, 255, 255, 255, 255, 1, 2, 3, 4, 5, 6, 1,
+//!     2, 3, 4, 5, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 100, 0, 1, 0, 0, 4, 116, 101, 115, 116,
+//! ];
+//!
+//! let (radiotap, rest) = Radiotap::parse(&capture).unwrap();
+//! let frame = Frame::parse(&radiotap, rest).unwrap();
+//!
+//! if let ManagementFrame::Beacon(beacon) = ManagementFrame::parse(&frame).unwrap() {
+//!     for element in &beacon.elements {
+//!         match element {
+//!             Ok((ElementKind::Ssid, data)) => {
+//!                 let ssid: Ssid = from_bytes(data).unwrap();
+//!                 println!("{:?}", ssid);
+//!             }
+//!             _ => {}
+//!         }
+//!     }
+//! }
+//! ```
+
+pub mod element;
+
+use byteorder::{ReadBytesExt, LE};
+use std::io::{Cursor, Read};
+
+use crate::{
+    ieee80211::{mgmt::element::ElementIterator, Frame, FrameType, MacAddress},
+    Error, Result,
+};
+
+/// The body of a beacon or probe response frame.
+#[derive(Clone, Copy, Debug)]
+pub struct Beacon<'a> {
+    /// The TSF timer of the transmitter in microseconds.
+    pub timestamp: u64,
+    /// The number of time units (1024 microseconds) between beacons.
+    pub beacon_interval: u16,
+    /// The capability information field.
+    pub capabilities: u16,
+    /// The information elements.
+    pub elements: ElementIterator<'a>,
+}
+
+/// The body of a probe request frame.
+#[derive(Clone, Copy, Debug)]
+pub struct ProbeRequest<'a> {
+    /// The information elements.
+    pub elements: ElementIterator<'a>,
+}
+
+/// The body of an association or reassociation request frame.
+#[derive(Clone, Copy, Debug)]
+pub struct AssociationRequest<'a> {
+    /// The capability information field.
+    pub capabilities: u16,
+    /// The number of beacon intervals between the STA waking up to receive
+    /// beacons.
+    pub listen_interval: u16,
+    /// The AP the STA is currently associated with. Only present in
+    /// reassociation requests.
+    pub current_ap: Option<MacAddress>,
+    /// The information elements.
+    pub elements: ElementIterator<'a>,
+}
+
+/// The body of an association or reassociation response frame.
+#[derive(Clone, Copy, Debug)]
+pub struct AssociationResponse<'a> {
+    /// The capability information field.
+    pub capabilities: u16,
+    /// The status code, 0 for success.
+    pub status_code: u16,
+    /// The association ID assigned to the STA.
+    pub association_id: u16,
+    /// The information elements.
+    pub elements: ElementIterator<'a>,
+}
+
+/// The body of an authentication frame.
+#[derive(Clone, Copy, Debug)]
+pub struct Authentication<'a> {
+    /// The authentication algorithm, 0 for open system and 3 for SAE.
+    pub algorithm: u16,
+    /// The transaction sequence number.
+    pub transaction_sequence: u16,
+    /// The status code, 0 for success.
+    pub status_code: u16,
+    /// The remaining body, which is algorithm dependent. Contains information
+    /// elements for open system and shared key authentication.
+    pub data: &'a [u8],
+}
+
+/// A parsed management frame body.
+#[derive(Clone, Copy, Debug)]
+pub enum ManagementFrame<'a> {
+    AssociationRequest(AssociationRequest<'a>),
+    AssociationResponse(AssociationResponse<'a>),
+    ReassociationRequest(AssociationRequest<'a>),
+    ReassociationResponse(AssociationResponse<'a>),
+    ProbeRequest(ProbeRequest<'a>),
+    ProbeResponse(Beacon<'a>),
+    Beacon(Beacon<'a>),
+    Authentication(Authentication<'a>),
+    /// A management frame this crate does not parse, with its subtype.
+    Other(u8),
+}
+
+impl<'a> ManagementFrame<'a> {
+    /// Returns the parsed body of a management frame.
+    pub fn parse(frame: &Frame<'a>) -> Result<ManagementFrame<'a>> {
+        let fc = frame.header.frame_control;
+        if fc.frame_type != FrameType::Management {
+            return Err(Error::InvalidFormat);
+        }
+        ManagementFrame::from_bytes(frame.body, fc.subtype)
+    }
+
+    /// Returns the parsed management frame body of the given subtype.
+    pub fn from_bytes(input: &'a [u8], subtype: u8) -> Result<ManagementFrame<'a>> {
+        let mut cursor = Cursor::new(input);
+
+        let frame = match subtype {
+            0 | 2 => {
+                let capabilities = cursor.read_u16::<LE>()?;
+                let listen_interval = cursor.read_u16::<LE>()?;
+                let current_ap = if subtype == 2 {
+                    let mut address = [0; 6];
+                    cursor.read_exact(&mut address)?;
+                    Some(address)
+                } else {
+                    None
+                };
+                let request = AssociationRequest {
+                    capabilities,
+                    listen_interval,
+                    current_ap,
+                    elements: elements(&cursor),
+                };
+                if subtype == 0 {
+                    ManagementFrame::AssociationRequest(request)
+                } else {
+                    ManagementFrame::ReassociationRequest(request)
+                }
+            }
+            1 | 3 => {
+                let response = AssociationResponse {
+                    capabilities: cursor.read_u16::<LE>()?,
+                    status_code: cursor.read_u16::<LE>()?,
+                    // The two most significant bits are always set
+                    association_id: cursor.read_u16::<LE>()? & 0x3fff,
+                    elements: elements(&cursor),
+                };
+                if subtype == 1 {
+                    ManagementFrame::AssociationResponse(response)
+                } else {
+                    ManagementFrame::ReassociationResponse(response)
+                }
+            }
+            4 => ManagementFrame::ProbeRequest(ProbeRequest {
+                elements: elements(&cursor),
+            }),
+            5 | 8 => {
+                let beacon = Beacon {
+                    timestamp: cursor.read_u64::<LE>()?,
+                    beacon_interval: cursor.read_u16::<LE>()?,
+                    capabilities: cursor.read_u16::<LE>()?,
+                    elements: elements(&cursor),
+                };
+                if subtype == 5 {
+                    ManagementFrame::ProbeResponse(beacon)
+                } else {
+                    ManagementFrame::Beacon(beacon)
+                }
+            }
+            11 => {
+                let algorithm = cursor.read_u16::<LE>()?;
+                let transaction_sequence = cursor.read_u16::<LE>()?;
+                let status_code = cursor.read_u16::<LE>()?;
+                ManagementFrame::Authentication(Authentication {
+                    algorithm,
+                    transaction_sequence,
+                    status_code,
+                    data: &input[cursor.position() as usize..],
+                })
+            }
+            subtype => ManagementFrame::Other(subtype),
+        };
+
+        Ok(frame)
+    }
+
+    /// Returns the information elements of the frame, if it has any.
+    pub fn elements(&self) -> Option<ElementIterator<'a>> {
+        match self {
+            ManagementFrame::AssociationRequest(f) | ManagementFrame::ReassociationRequest(f) => {
+                Some(f.elements)
+            }
+            ManagementFrame::AssociationResponse(f) | ManagementFrame::ReassociationResponse(f) => {
+                Some(f.elements)
+            }
+            ManagementFrame::ProbeRequest(f) => Some(f.elements),
+            ManagementFrame::ProbeResponse(f) | ManagementFrame::Beacon(f) => Some(f.elements),
+            ManagementFrame::Authentication(f) => Some(ElementIterator::from_bytes(f.data)),
+            ManagementFrame::Other(_) => None,
+        }
+    }
+}
+
+fn elements<'a>(cursor: &Cursor<&'a [u8]>) -> ElementIterator<'a> {
+    let input: &'a [u8] = cursor.get_ref();
+    ElementIterator::from_bytes(&input[cursor.position() as usize..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{element::*, *};
+
+    fn beacon_body() -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0x0102_0304u64.to_le_bytes());
+        body.extend_from_slice(&100u16.to_le_bytes());
+        body.extend_from_slice(&0x0411u16.to_le_bytes());
+        body.extend_from_slice(&[0, 4, b't', b'e', b's', b't']);
+        body.extend_from_slice(&[1, 4, 0x82, 0x84, 0x0c, 0x12]);
+        body.extend_from_slice(&[3, 1, 6]);
+        body.extend_from_slice(&[61, 22, 6, 0x05, 0, 0]);
+        body.extend_from_slice(&[0; 18]);
+        body.extend_from_slice(&[
+            48, 20, 1, 0, 0x00, 0x0f, 0xac, 4, 1, 0, 0x00, 0x0f, 0xac, 4, 1, 0, 0x00, 0x0f, 0xac,
+            2, 0x0c, 0x00,
+        ]);
+        body.extend_from_slice(&[221, 5, 0x00, 0x50, 0xf2, 2, 1]);
+        body
+    }
+
+    #[test]
+    fn beacon() {
+        let body = beacon_body();
+        let beacon = match ManagementFrame::from_bytes(&body, 8).unwrap() {
+            ManagementFrame::Beacon(beacon) => beacon,
+            f => panic!("Frame not Beacon: {:?}", f),
+        };
+
+        assert_eq!(beacon.timestamp, 0x0102_0304);
+        assert_eq!(beacon.beacon_interval, 100);
+
+        let elements = beacon.elements;
+        let ssid: Ssid = elements.get(ElementKind::Ssid).unwrap().unwrap();
+        assert_eq!(ssid.value, b"test");
+
+        let rates: SupportedRates = elements.get(ElementKind::SupportedRates).unwrap().unwrap();
+        assert_eq!(
+            rates.rates[0],
+            SupportedRate {
+                rate: 1.0,
+                basic: true
+            }
+        );
+        assert_eq!(rates.rates[3].rate, 9.0);
+
+        let ds: DsParameterSet = elements.get(ElementKind::DsParameterSet).unwrap().unwrap();
+        assert_eq!(ds.channel, 6);
+
+        let ht: HtOperation = elements.get(ElementKind::HtOperation).unwrap().unwrap();
+        assert_eq!(ht.primary_channel, 6);
+        assert_eq!(ht.secondary_channel_offset, SecondaryChannelOffset::Above);
+        assert!(ht.any_channel_width);
+
+        let rsn: Rsn = elements.get(ElementKind::Rsn).unwrap().unwrap();
+        assert_eq!(rsn.version, 1);
+        assert_eq!(rsn.pairwise_ciphers.len(), 1);
+        assert_eq!(rsn.akm_suites[0].suite_type, 2);
+        assert_eq!(rsn.capabilities, Some(0x000c));
+
+        let vendor: VendorSpecific = elements.get(ElementKind::VendorSpecific).unwrap().unwrap();
+        assert_eq!(vendor.oui, [0x00, 0x50, 0xf2]);
+        assert_eq!(vendor.data, &[2, 1]);
+
+        assert_eq!(elements.into_iter().count(), 6);
+    }
+
+    #[test]
+    fn he_operation_6ghz() {
+        let body = [
+            255, 12, 36, 0x00, 0x00, 0x02, 0x05, 0xfc, 0xff, 37, 0x02, 39, 0, 6,
+        ];
+        let (kind, data) = ElementIterator::from_bytes(&body)
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(kind, ElementKind::HeOperation);
+
+        let he: HeOperation = from_bytes(data).unwrap();
+        let six_ghz = he.six_ghz_operation.unwrap();
+        assert_eq!(he.bss_color, 5);
+        assert_eq!(he.vht_operation, None);
+        assert_eq!(six_ghz.primary_channel, 37);
+        assert_eq!(six_ghz.channel_width, 2);
+        assert_eq!(six_ghz.center_freq_seg0, 39);
+    }
+
+    #[test]
+    fn truncated_element() {
+        let body = [0, 4, b'a', 3, 1];
+        let mut iter = ElementIterator::from_bytes(&body).into_iter();
+
+        match iter.next().unwrap().unwrap_err() {
+            Error::IncompleteError => {}
+            e => panic!("Error not IncompleteError: {:?}", e),
+        };
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn association_response() {
+        let body = [0x11, 0x04, 0, 0, 0x01, 0xc0, 0, 0];
+        match ManagementFrame::from_bytes(&body, 1).unwrap() {
+            ManagementFrame::AssociationResponse(response) => {
+                assert_eq!(response.status_code, 0);
+                assert_eq!(response.association_id, 1);
+                assert_eq!(response.elements.into_iter().count(), 1);
+            }
+            f => panic!("Frame not AssociationResponse: {:?}", f),
+        }
+    }
+}
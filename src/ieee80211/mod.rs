@@ -0,0 +1,318 @@
+//! Parsers for the IEEE 802.11 frame following the Radiotap header.
+//!
+//! ```
+//! use radiotap::{ieee80211::Frame, Radiotap};
+//!
+//! let capture = [
+//!     0, 0, 9, 0, 2, 0, 0, 0, 16, 212, 0, 0, 0, 1, 2, 3, 4, 5, 6, 0, 0, 0, 0,
+//! ];
+//!
+//! let (radiotap, rest) = Radiotap::parse(&capture).unwrap();
+//! let frame = Frame::parse(&radiotap, rest).unwrap();
+//! println!("{:?}", frame.header.frame_control);
+//! ```
+
+pub mod analysis;
+pub mod block_ack;
+pub mod fcs;
+pub mod mgmt;
+
+use bitops::BitOps;
+use byteorder::{ReadBytesExt, LE};
+use std::io::{Cursor, Read};
+
+use crate::{Error, Radiotap, Result};
+
+/// A 48-bit IEEE MAC address.
+pub type MacAddress = [u8; 6];
+
+/// The type of an 802.11 frame.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FrameType {
+    Management,
+    Control,
+    Data,
+    Extension,
+}
+
+/// The frame control field, the first two bytes of every 802.11 frame.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FrameControl {
+    /// The protocol version, currently always 0.
+    pub protocol_version: u8,
+    /// The frame type.
+    pub frame_type: FrameType,
+    /// The frame subtype, its meaning depends on the frame type.
+    pub subtype: u8,
+    /// The frame is destined for the distribution system.
+    pub to_ds: bool,
+    /// The frame is leaving the distribution system.
+    pub from_ds: bool,
+    /// More fragments of the current MSDU or MMPDU follow.
+    pub more_fragments: bool,
+    /// The frame is a retransmission.
+    pub retry: bool,
+    /// The STA will be in power save mode after this frame.
+    pub power_management: bool,
+    /// The AP has more buffered frames for the STA.
+    pub more_data: bool,
+    /// The frame body is encrypted.
+    pub protected: bool,
+    /// The frame is strictly ordered, or contains an HT Control field.
+    pub order: bool,
+}
+
+impl FrameControl {
+    pub fn new(value: u16) -> FrameControl {
+        FrameControl {
+            protocol_version: value.bits_as_int(0, 2) as u8,
+            frame_type: match value.bits_as_int(2, 2) {
+                0 => FrameType::Management,
+                1 => FrameType::Control,
+                2 => FrameType::Data,
+                _ => FrameType::Extension,
+            },
+            subtype: value.bits_as_int(4, 4) as u8,
+            to_ds: value.is_flag_set(0x0100),
+            from_ds: value.is_flag_set(0x0200),
+            more_fragments: value.is_flag_set(0x0400),
+            retry: value.is_flag_set(0x0800),
+            power_management: value.is_flag_set(0x1000),
+            more_data: value.is_flag_set(0x2000),
+            protected: value.is_flag_set(0x4000),
+            order: value.is_flag_set(0x8000),
+        }
+    }
+
+    /// Whether this is a QoS data frame, which includes a QoS Control field.
+    pub fn is_qos_data(&self) -> bool {
+        self.frame_type == FrameType::Data && self.subtype.is_flag_set(0x08)
+    }
+}
+
+/// The sequence control field.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SequenceControl {
+    /// The fragment number.
+    pub fragment: u8,
+    /// The sequence number.
+    pub sequence: u16,
+}
+
+/// The 802.11 MAC header.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MacHeader {
+    /// The frame control field.
+    pub frame_control: FrameControl,
+    /// The duration or association ID.
+    pub duration: u16,
+    /// The first address, usually the receiver.
+    pub addr1: MacAddress,
+    /// The second address, usually the transmitter. Absent in ACK and CTS
+    /// frames.
+    pub addr2: Option<MacAddress>,
+    /// The third address. Only present in management and data frames.
+    pub addr3: Option<MacAddress>,
+    /// The sequence control field. Only present in management and data frames.
+    pub sequence_control: Option<SequenceControl>,
+    /// The fourth address. Only present in data frames between distribution
+    /// systems.
+    pub addr4: Option<MacAddress>,
+    /// The QoS control field. Only present in QoS data frames.
+    pub qos_control: Option<u16>,
+    /// The HT control field.
+    pub ht_control: Option<u32>,
+    /// The length of the MAC header in bytes.
+    pub length: usize,
+}
+
+impl MacHeader {
+    /// Returns the parsed MAC header from the start of an 802.11 frame.
+    pub fn from_bytes(input: &[u8]) -> Result<MacHeader> {
+        let mut cursor = Cursor::new(input);
+        let frame_control = FrameControl::new(cursor.read_u16::<LE>()?);
+        let duration = cursor.read_u16::<LE>()?;
+        let addr1 = read_address(&mut cursor)?;
+
+        let mut header = MacHeader {
+            frame_control,
+            duration,
+            addr1,
+            addr2: None,
+            addr3: None,
+            sequence_control: None,
+            addr4: None,
+            qos_control: None,
+            ht_control: None,
+            length: 0,
+        };
+
+        match frame_control.frame_type {
+            FrameType::Management | FrameType::Data => {
+                header.addr2 = Some(read_address(&mut cursor)?);
+                header.addr3 = Some(read_address(&mut cursor)?);
+                let sequence = cursor.read_u16::<LE>()?;
+                header.sequence_control = Some(SequenceControl {
+                    fragment: sequence.bits_as_int(0, 4) as u8,
+                    sequence: sequence >> 4,
+                });
+
+                if frame_control.to_ds && frame_control.from_ds {
+                    header.addr4 = Some(read_address(&mut cursor)?);
+                }
+                if frame_control.is_qos_data() {
+                    header.qos_control = Some(cursor.read_u16::<LE>()?);
+                }
+
+                // Only management and QoS data frames can carry HT control
+                let ht_control = frame_control.frame_type == FrameType::Management
+                    || frame_control.is_qos_data();
+                if frame_control.order && ht_control {
+                    header.ht_control = Some(cursor.read_u32::<LE>()?);
+                }
+            }
+            FrameType::Control => match frame_control.subtype {
+                // CTS and ACK
+                12 | 13 => {}
+                // Control wrapper, the carried frame control is skipped
+                7 => {
+                    cursor.read_u16::<LE>()?;
+                    header.ht_control = Some(cursor.read_u32::<LE>()?);
+                }
+                _ => header.addr2 = Some(read_address(&mut cursor)?),
+            },
+            FrameType::Extension => {}
+        }
+
+        header.length = cursor.position() as usize;
+        Ok(header)
+    }
+
+    /// Returns the address of the transmitter, if the frame has one.
+    pub fn transmitter(&self) -> Option<MacAddress> {
+        self.addr2
+    }
+
+    /// Returns the address of the receiver.
+    pub fn receiver(&self) -> MacAddress {
+        self.addr1
+    }
+
+    /// Returns the BSSID of management and data frames.
+    pub fn bssid(&self) -> Option<MacAddress> {
+        let fc = &self.frame_control;
+        match (fc.frame_type, fc.to_ds, fc.from_ds) {
+            (FrameType::Management, _, _) | (FrameType::Data, false, false) => self.addr3,
+            (FrameType::Data, true, false) => Some(self.addr1),
+            (FrameType::Data, false, true) => self.addr2,
+            _ => None,
+        }
+    }
+}
+
+fn read_address(cursor: &mut Cursor<&[u8]>) -> Result<MacAddress> {
+    let mut address = [0; 6];
+    cursor.read_exact(&mut address)?;
+    Ok(address)
+}
+
+/// An 802.11 frame split into its MAC header, body, and FCS.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Frame<'a> {
+    /// The parsed MAC header.
+    pub header: MacHeader,
+    /// The frame body, excluding any padding and the FCS.
+    pub body: &'a [u8],
+    /// The frame check sequence, if the capture includes it.
+    pub fcs: Option<u32>,
+}
+
+impl<'a> Frame<'a> {
+    /// Returns the parsed 802.11 frame from the data following the Radiotap
+    /// header, using the Radiotap flags to find any padding and the FCS.
+    pub fn parse(radiotap: &Radiotap, input: &'a [u8]) -> Result<Frame<'a>> {
+        let (data_pad, fcs) = match radiotap.flags {
+            Some(flags) => (flags.data_pad, flags.fcs),
+            None => (false, false),
+        };
+        Frame::from_bytes(input, data_pad, fcs)
+    }
+
+    /// Returns the parsed 802.11 frame. If `data_pad` is set the body starts
+    /// at the next 32-bit boundary after the MAC header, if `fcs` is set the
+    /// last four bytes are the FCS.
+    pub fn from_bytes(input: &'a [u8], data_pad: bool, fcs: bool) -> Result<Frame<'a>> {
+        let (input, fcs) = if fcs {
+            if input.len() < 4 {
+                return Err(Error::IncompleteError);
+            }
+            let (input, fcs) = input.split_at(input.len() - 4);
+            (input, Some(Cursor::new(fcs).read_u32::<LE>()?))
+        } else {
+            (input, None)
+        };
+
+        let header = MacHeader::from_bytes(input)?;
+
+        let start = if data_pad {
+            (header.length + 3) & !3
+        } else {
+            header.length
+        };
+        let body = input.get(start..).ok_or(Error::IncompleteError)?;
+
+        Ok(Frame { header, body, fcs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qos_data_with_padding_and_fcs() {
+        let frame = [
+            0x88, 0x02, 0x2c, 0x00, // QoS data, from DS
+            1, 1, 1, 1, 1, 1, // addr1
+            2, 2, 2, 2, 2, 2, // addr2
+            3, 3, 3, 3, 3, 3, // addr3
+            0x10, 0x00, // sequence 1
+            0x05, 0x00, // QoS control
+            0, 0, // padding
+            0xaa, 0xbb, // body
+            0x78, 0x56, 0x34, 0x12, // FCS
+        ];
+
+        let frame = Frame::from_bytes(&frame, true, true).unwrap();
+        let fc = frame.header.frame_control;
+
+        assert_eq!(fc.frame_type, FrameType::Data);
+        assert_eq!(fc.subtype, 8);
+        assert!(fc.from_ds && !fc.to_ds);
+        assert_eq!(frame.header.length, 26);
+        assert_eq!(frame.header.qos_control, Some(5));
+        assert_eq!(frame.header.sequence_control.unwrap().sequence, 1);
+        assert_eq!(frame.header.bssid(), Some([2; 6]));
+        assert_eq!(frame.body, &[0xaa, 0xbb]);
+        assert_eq!(frame.fcs, Some(0x1234_5678));
+    }
+
+    #[test]
+    fn ack() {
+        let frame = [0xd4, 0x00, 0x00, 0x00, 1, 2, 3, 4, 5, 6];
+        let frame = Frame::from_bytes(&frame, false, false).unwrap();
+
+        assert_eq!(frame.header.frame_control.frame_type, FrameType::Control);
+        assert_eq!(frame.header.addr1, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(frame.header.transmitter(), None);
+        assert_eq!(frame.body, &[] as &[u8]);
+    }
+
+    #[test]
+    fn truncated() {
+        match MacHeader::from_bytes(&[0x08, 0x00, 0x00]).unwrap_err() {
+            Error::ParseError(_) => {}
+            e => panic!("Error not ParseError: {:?}", e),
+        };
+    }
+}
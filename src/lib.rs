@@ -41,11 +41,27 @@
 //! }
 //! ```
 
+pub mod airtime;
+pub mod ampdu;
+pub mod capture;
+pub mod channel;
+#[cfg(feature = "arrow")]
+pub mod columnar;
+pub mod convert;
+pub mod export;
 pub mod field;
+pub mod filter;
+#[cfg(feature = "ieee80211")]
+pub mod ieee80211;
+pub mod phy;
+pub mod schema;
+pub mod stats;
+pub mod time;
+pub mod units;
 
 use std::{io::Cursor, result};
 
-use crate::field::*;
+use crate::field::{encode::Encode, *};
 
 /// All errors returned and used by the radiotap module.
 #[derive(Debug, thiserror::Error)]
@@ -73,6 +89,31 @@ pub enum Error {
     /// Unsupported Radiotap field.
     #[error("unsupported radiotap field")]
     UnsupportedField,
+
+    /// The given data is not a valid capture file.
+    #[error("invalid capture file")]
+    InvalidCaptureFile,
+
+    /// The capture uses a link type other than the one expected.
+    #[error("unsupported link type {0}")]
+    UnsupportedLinkType(u32),
+
+    /// The packet cannot be represented in the capture format being written.
+    #[error("cannot write packet: {0}")]
+    UnwritablePacket(&'static str),
+
+    /// The filter expression could not be parsed.
+    #[error("invalid filter: {0}")]
+    InvalidFilter(String),
+
+    /// The export column does not exist.
+    #[error("unknown column `{0}`")]
+    UnknownColumn(String),
+
+    /// An Arrow record batch could not be built or a Parquet file could not be
+    /// written.
+    #[error(transparent)]
+    ColumnarError(Box<dyn std::error::Error + Send + Sync>),
 }
 
 type Result<T> = result::Result<T, Error>;
@@ -272,12 +313,78 @@ impl Radiotap {
 
         Ok((radiotap, rest))
     }
+
+    /// Returns the Radiotap capture encoding all present fields. The header is
+    /// generated from the present fields, so the `header` member and any
+    /// vendor namespaces are ignored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        fn encode<T: Encode>(field: &Option<T>) -> Option<&dyn Encode> {
+            field.as_ref().map(|f| f as &dyn Encode)
+        }
+
+        let fields = [
+            (Kind::TSFT, encode(&self.tsft)),
+            (Kind::Flags, encode(&self.flags)),
+            (Kind::Rate, encode(&self.rate)),
+            (Kind::Channel, encode(&self.channel)),
+            (Kind::FHSS, encode(&self.fhss)),
+            (Kind::AntennaSignal, encode(&self.antenna_signal)),
+            (Kind::AntennaNoise, encode(&self.antenna_noise)),
+            (Kind::LockQuality, encode(&self.lock_quality)),
+            (Kind::TxAttenuation, encode(&self.tx_attenuation)),
+            (Kind::TxAttenuationDb, encode(&self.tx_attenuation_db)),
+            (Kind::TxPower, encode(&self.tx_power)),
+            (Kind::Antenna, encode(&self.antenna)),
+            (Kind::AntennaSignalDb, encode(&self.antenna_signal_db)),
+            (Kind::AntennaNoiseDb, encode(&self.antenna_noise_db)),
+            (Kind::RxFlags, encode(&self.rx_flags)),
+            (Kind::TxFlags, encode(&self.tx_flags)),
+            (Kind::RTSRetries, encode(&self.rts_retries)),
+            (Kind::DataRetries, encode(&self.data_retries)),
+            (Kind::XChannel, encode(&self.xchannel)),
+            (Kind::MCS, encode(&self.mcs)),
+            (Kind::AMPDUStatus, encode(&self.ampdu_status)),
+            (Kind::VHT, encode(&self.vht)),
+            (Kind::Timestamp, encode(&self.timestamp)),
+        ];
+
+        // Version, padding, length, and a single present word
+        let mut out = vec![0; 8];
+        let mut present = 0u32;
+
+        for (kind, field) in fields.iter() {
+            if let (Some(field), Some(bit)) = (field, kind.value()) {
+                let align = kind.align() as usize;
+                out.resize((out.len() + align - 1) & !(align - 1), 0);
+                field.encode(&mut out);
+                present |= 1 << bit;
+            }
+        }
+
+        let length = out.len() as u16;
+        out[2..4].copy_from_slice(&length.to_le_bytes());
+        out[4..8].copy_from_slice(&present.to_le_bytes());
+        out
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// A capture of a VHT frame with the TSFT, Flags, Channel, AntennaSignal,
+    /// AntennaNoise, Antenna, XChannel, AMPDUStatus, and VHT fields.
+    pub(crate) const VHT_CAPTURE: [u8; 56] = [
+        0, 0, 56, 0, 107, 8, 52, 0, 185, 31, 155, 154, 0, 0, 0, 0, 20, 0, 124, 21, 64, 1, 213, 166,
+        1, 0, 0, 0, 64, 1, 1, 0, 124, 21, 100, 34, 249, 1, 0, 0, 0, 0, 0, 0, 255, 1, 80, 4, 115, 0,
+        0, 0, 1, 63, 0, 0,
+    ];
+
+    /// Returns the parsed VHT capture, for the tests of other modules.
+    pub(crate) fn vht_radiotap() -> Radiotap {
+        Radiotap::from_bytes(&VHT_CAPTURE).unwrap()
+    }
+
     #[test]
     fn good_vendor() {
         let frame = [
@@ -291,6 +398,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn to_bytes_round_trip() {
+        let radiotap = vht_radiotap();
+        let bytes = radiotap.to_bytes();
+
+        assert_eq!(bytes.len(), VHT_CAPTURE.len());
+        assert_eq!(Radiotap::from_bytes(&bytes).unwrap(), radiotap);
+    }
+
     #[test]
     fn bad_version() {
         let frame = [
@@ -0,0 +1,157 @@
+//! Inference of the PHY that produced a frame.
+
+use crate::{channel::Band, field::Header, Radiotap, Result};
+
+// Bit numbers of fields this crate does not parse
+const HE: u8 = 23;
+const HE_MU: u8 = 24;
+const S1G: u8 = 32;
+const U_SIG: u8 = 33;
+const EHT: u8 = 34;
+
+/// The PHY used to transmit a frame.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Phy {
+    /// 802.11 direct sequence spread spectrum, 1 and 2 Mbps.
+    Dsss,
+    /// 802.11b high rate DSSS, 5.5 and 11 Mbps.
+    HrDsss,
+    /// 802.11g OFDM in the 2.4 GHz band.
+    ErpOfdm,
+    /// 802.11a OFDM.
+    Ofdm,
+    /// 802.11n high throughput.
+    Ht,
+    /// 802.11ac very high throughput.
+    Vht,
+    /// 802.11ax high efficiency.
+    He,
+    /// 802.11be extremely high throughput.
+    Eht,
+    /// 802.11ah sub-1 GHz.
+    S1g,
+}
+
+/// Returns the PHY that produced a captured frame.
+///
+/// Unlike [Radiotap::phy](../struct.Radiotap.html#method.phy), HE, EHT, and
+/// S1G frames are also recognized by the presence of their fields, which are
+/// not parsed.
+pub fn infer(capture: &[u8]) -> Result<Option<Phy>> {
+    let unsupported = Header::unsupported(capture)?;
+    if unsupported.contains(&S1G) {
+        return Ok(Some(Phy::S1g));
+    }
+    if unsupported.contains(&EHT) || unsupported.contains(&U_SIG) {
+        return Ok(Some(Phy::Eht));
+    }
+    if unsupported.contains(&HE) || unsupported.contains(&HE_MU) {
+        return Ok(Some(Phy::He));
+    }
+    Ok(Radiotap::from_bytes(capture)?.phy())
+}
+
+impl Radiotap {
+    /// Returns the PHY that produced the frame, from the VHT and MCS fields, or
+    /// for legacy frames from the rate together with the channel flags and
+    /// preamble. Use [infer](phy/fn.infer.html) to also recognize HE, EHT, and
+    /// S1G frames.
+    pub fn phy(&self) -> Option<Phy> {
+        if self.vht.is_some() {
+            return Some(Phy::Vht);
+        }
+        if self.mcs.is_some() {
+            return Some(Phy::Ht);
+        }
+
+        let (cck, ofdm, ghz2) = match (self.xchannel, self.channel) {
+            (Some(x), _) => (
+                x.flags.cck,
+                x.flags.ofdm,
+                x.flags.ghz2 || x.band() == Some(Band::Ghz2),
+            ),
+            (None, Some(c)) => (
+                c.flags.cck,
+                c.flags.ofdm,
+                c.flags.ghz2 || c.band() == Some(Band::Ghz2),
+            ),
+            (None, None) => (false, false, false),
+        };
+        let short_preamble = matches!(self.flags, Some(flags) if flags.preamble);
+        let ofdm_phy = if ghz2 { Phy::ErpOfdm } else { Phy::Ofdm };
+
+        // The rate is in units of 500 kbps
+        let rate = (self.rate?.value * 2.0) as u32;
+        match rate {
+            2 => Some(Phy::Dsss),
+            4 if short_preamble => Some(Phy::HrDsss),
+            4 => Some(Phy::Dsss),
+            11 | 22 | 44 | 66 => Some(Phy::HrDsss),
+            12 | 18 | 24 | 36 | 48 | 72 | 96 | 108 => Some(ofdm_phy),
+            // Half and quarter clocked OFDM, or a rate we do not know
+            _ if ofdm => Some(ofdm_phy),
+            _ if cck => Some(Phy::HrDsss),
+            _ => None,
+        }
+    }
+
+    /// Returns the data rate of the frame in Mbps, from the VHT, MCS, or Rate
+    /// field, in that order.
+    pub fn data_rate_mbps(&self) -> Option<f32> {
+        if let Some(vht) = self.vht {
+            if let Some(rate) = vht.users.iter().flatten().find_map(|u| u.datarate) {
+                return Some(rate);
+            }
+        }
+        if let Some(rate) = self.mcs.and_then(|mcs| mcs.datarate) {
+            return Some(rate);
+        }
+        self.rate.map(|rate| rate.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::*;
+
+    #[test]
+    fn legacy() {
+        let capture = [
+            0, 0, 18, 0, 46, 72, 0, 0, 0, 2, 108, 9, 160, 0, 228, 0, 0, 0,
+        ];
+        let radiotap = Radiotap::from_bytes(&capture).unwrap();
+        assert_eq!(radiotap.phy(), Some(Phy::Dsss));
+        assert_eq!(radiotap.data_rate_mbps(), Some(1.0));
+
+        let ofdm = Radiotap {
+            rate: Some(Rate { value: 24.0 }),
+            ..radiotap.clone()
+        };
+        assert_eq!(ofdm.phy(), Some(Phy::ErpOfdm));
+    }
+
+    #[test]
+    fn ht() {
+        let radiotap = Radiotap {
+            mcs: Some(MCS {
+                datarate: Some(65.0),
+                ..Default::default()
+            }),
+            rate: Some(Rate { value: 6.0 }),
+            ..Default::default()
+        };
+        assert_eq!(radiotap.phy(), Some(Phy::Ht));
+        assert_eq!(radiotap.data_rate_mbps(), Some(65.0));
+    }
+
+    #[test]
+    fn he_present() {
+        // Radiotap header with only the HE field present
+        let mut capture = vec![0, 0, 20, 0, 0, 0, 0x80, 0];
+        capture.extend_from_slice(&[0; 12]);
+        assert_eq!(Header::unsupported(&capture).unwrap(), vec![HE]);
+        assert_eq!(infer(&capture).unwrap(), Some(Phy::He));
+        assert_eq!(Radiotap::from_bytes(&capture).unwrap().phy(), None);
+    }
+}
@@ -0,0 +1,408 @@
+//! A flat description of every member of the parsed fields.
+//!
+//! Each member is named by its path in the [Radiotap](../struct.Radiotap.html)
+//! struct, such as `channel.freq` or `vht.users[0].nss`. Fields with a single
+//! `value` member are named after the field, such as `tsft`. The filters, the
+//! CSV and JSON Lines export, and the Arrow export are all built from this
+//! description, so they always agree on the members and their types.
+//!
+//! ```
+//! use radiotap::{field::TSFT, schema, Radiotap};
+//!
+//! let radiotap = Radiotap {
+//!     tsft: Some(TSFT { value: 1234 }),
+//!     ..Default::default()
+//! };
+//!
+//! let tsft = schema::member("tsft").unwrap();
+//! assert_eq!(tsft.get(&radiotap), Some(schema::Value::UInt(1234)));
+//! ```
+
+use std::fmt;
+
+use crate::{
+    field::{
+        ext::{Bandwidth, GuardInterval, HTFormat, SamplingPosition, TimeUnit, VHTUser, FEC},
+        VHT,
+    },
+    Radiotap,
+};
+
+/// The type of a member.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Type {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    F32,
+    Bool,
+    /// The name of an enum variant, such as `Short` for the guard interval.
+    Str,
+}
+
+/// The value of a member.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Value {
+    UInt(u64),
+    Int(i64),
+    Float(f32),
+    Bool(bool),
+    Str(&'static str),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::UInt(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// A member of a field.
+pub struct Member {
+    path: &'static str,
+    ty: Type,
+    get: fn(&Radiotap) -> Option<Value>,
+}
+
+impl Member {
+    /// Returns the path of the member, such as `channel.freq`.
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// Returns the type of the member.
+    pub fn ty(&self) -> Type {
+        self.ty
+    }
+
+    /// Returns the value of the member, or `None` if it is not present.
+    pub fn get(&self, radiotap: &Radiotap) -> Option<Value> {
+        (self.get)(radiotap)
+    }
+}
+
+impl fmt::Debug for Member {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Member")
+            .field("path", &self.path)
+            .field("ty", &self.ty)
+            .finish()
+    }
+}
+
+/// Returns every member, in the order of the fields in the Radiotap struct.
+pub fn members() -> &'static [Member] {
+    MEMBERS
+}
+
+/// Returns the member at the path.
+pub fn member(path: &str) -> Option<&'static Member> {
+    MEMBERS.iter().find(|member| member.path == path)
+}
+
+/// Returns whether the field or struct member at the path, such as `channel`
+/// or `vht.users[0]`, is present. Returns `None` if there is no field or
+/// struct member at the path.
+pub fn is_present(path: &str, radiotap: &Radiotap) -> Option<bool> {
+    presence(path).map(|present| present(radiotap))
+}
+
+/// Returns the paths of the fields and struct members that contain other
+/// members, with their presence tests.
+#[cfg(feature = "arrow")]
+pub(crate) fn structs() -> &'static [(&'static str, Presence)] {
+    STRUCTS
+}
+
+/// Returns the presence test of the field or struct member at the path.
+pub(crate) fn presence(path: &str) -> Option<Presence> {
+    STRUCTS
+        .iter()
+        .find(|(p, _)| *p == path)
+        .map(|(_, present)| *present)
+}
+
+trait Name {
+    fn name(self) -> &'static str;
+}
+
+impl Name for GuardInterval {
+    fn name(self) -> &'static str {
+        match self {
+            GuardInterval::Long => "Long",
+            GuardInterval::Short => "Short",
+        }
+    }
+}
+
+impl Name for FEC {
+    fn name(self) -> &'static str {
+        match self {
+            FEC::BCC => "BCC",
+            FEC::LDPC => "LDPC",
+        }
+    }
+}
+
+impl Name for HTFormat {
+    fn name(self) -> &'static str {
+        match self {
+            HTFormat::Mixed => "Mixed",
+            HTFormat::Greenfield => "Greenfield",
+        }
+    }
+}
+
+impl Name for TimeUnit {
+    fn name(self) -> &'static str {
+        match self {
+            TimeUnit::Milliseconds => "Milliseconds",
+            TimeUnit::Microseconds => "Microseconds",
+            TimeUnit::Nanoseconds => "Nanoseconds",
+        }
+    }
+}
+
+impl Name for SamplingPosition {
+    fn name(self) -> &'static str {
+        match self {
+            SamplingPosition::StartMPDU => "StartMPDU",
+            SamplingPosition::StartPLCP => "StartPLCP",
+            SamplingPosition::EndPPDU => "EndPPDU",
+            SamplingPosition::EndMPDU => "EndMPDU",
+            SamplingPosition::Unknown => "Unknown",
+        }
+    }
+}
+
+fn uint<T: Into<u64>>(value: Option<T>) -> Option<Value> {
+    value.map(|v| Value::UInt(v.into()))
+}
+
+fn int<T: Into<i64>>(value: Option<T>) -> Option<Value> {
+    value.map(|v| Value::Int(v.into()))
+}
+
+fn float(value: Option<f32>) -> Option<Value> {
+    value.map(Value::Float)
+}
+
+fn boolean(value: Option<bool>) -> Option<Value> {
+    value.map(Value::Bool)
+}
+
+fn name<T: Name>(value: Option<T>) -> Option<Value> {
+    value.map(|v| Value::Str(v.name()))
+}
+
+fn mcs_bw(radiotap: &Radiotap) -> Option<Bandwidth> {
+    radiotap.mcs.and_then(|mcs| mcs.bw)
+}
+
+fn vht_bw(radiotap: &Radiotap) -> Option<Bandwidth> {
+    radiotap.vht.and_then(|vht| vht.bw)
+}
+
+fn user<const I: usize>(radiotap: &Radiotap) -> Option<VHTUser> {
+    radiotap.vht.and_then(|vht: VHT| vht.users[I])
+}
+
+fn user_index<const I: usize>(r: &Radiotap) -> Option<Value> {
+    uint(user::<I>(r).map(|u| u.index))
+}
+
+fn user_fec<const I: usize>(r: &Radiotap) -> Option<Value> {
+    name(user::<I>(r).map(|u| u.fec))
+}
+
+fn user_nss<const I: usize>(r: &Radiotap) -> Option<Value> {
+    uint(user::<I>(r).map(|u| u.nss))
+}
+
+fn user_nsts<const I: usize>(r: &Radiotap) -> Option<Value> {
+    uint(user::<I>(r).map(|u| u.nsts))
+}
+
+fn user_datarate<const I: usize>(r: &Radiotap) -> Option<Value> {
+    float(user::<I>(r).and_then(|u| u.datarate))
+}
+
+macro_rules! member {
+    ($path:expr, $ty:ident, $get:expr) => {
+        Member {
+            path: $path,
+            ty: Type::$ty,
+            get: $get,
+        }
+    };
+}
+
+#[rustfmt::skip]
+const MEMBERS: &[Member] = &[
+    member!("tsft", U64, |r| uint(r.tsft.map(|f| f.value))),
+    member!("flags.cfp", Bool, |r| boolean(r.flags.map(|f| f.cfp))),
+    member!("flags.preamble", Bool, |r| boolean(r.flags.map(|f| f.preamble))),
+    member!("flags.wep", Bool, |r| boolean(r.flags.map(|f| f.wep))),
+    member!("flags.fragmentation", Bool, |r| boolean(r.flags.map(|f| f.fragmentation))),
+    member!("flags.fcs", Bool, |r| boolean(r.flags.map(|f| f.fcs))),
+    member!("flags.data_pad", Bool, |r| boolean(r.flags.map(|f| f.data_pad))),
+    member!("flags.bad_fcs", Bool, |r| boolean(r.flags.map(|f| f.bad_fcs))),
+    member!("flags.sgi", Bool, |r| boolean(r.flags.map(|f| f.sgi))),
+    member!("rate", F32, |r| float(r.rate.map(|f| f.value))),
+    member!("channel.freq", U16, |r| uint(r.channel.map(|f| f.freq))),
+    member!("channel.flags.turbo", Bool, |r| boolean(r.channel.map(|f| f.flags.turbo))),
+    member!("channel.flags.cck", Bool, |r| boolean(r.channel.map(|f| f.flags.cck))),
+    member!("channel.flags.ofdm", Bool, |r| boolean(r.channel.map(|f| f.flags.ofdm))),
+    member!("channel.flags.ghz2", Bool, |r| boolean(r.channel.map(|f| f.flags.ghz2))),
+    member!("channel.flags.ghz5", Bool, |r| boolean(r.channel.map(|f| f.flags.ghz5))),
+    member!("channel.flags.passive", Bool, |r| boolean(r.channel.map(|f| f.flags.passive))),
+    member!("channel.flags.dynamic", Bool, |r| boolean(r.channel.map(|f| f.flags.dynamic))),
+    member!("channel.flags.gfsk", Bool, |r| boolean(r.channel.map(|f| f.flags.gfsk))),
+    member!("fhss.hopset", U8, |r| uint(r.fhss.map(|f| f.hopset))),
+    member!("fhss.pattern", U8, |r| uint(r.fhss.map(|f| f.pattern))),
+    member!("antenna_signal", I8, |r| int(r.antenna_signal.map(|f| f.value))),
+    member!("antenna_noise", I8, |r| int(r.antenna_noise.map(|f| f.value))),
+    member!("lock_quality", U16, |r| uint(r.lock_quality.map(|f| f.value))),
+    member!("tx_attenuation", U16, |r| uint(r.tx_attenuation.map(|f| f.value))),
+    member!("tx_attenuation_db", U16, |r| uint(r.tx_attenuation_db.map(|f| f.value))),
+    member!("tx_power", I8, |r| int(r.tx_power.map(|f| f.value))),
+    member!("antenna", U8, |r| uint(r.antenna.map(|f| f.value))),
+    member!("antenna_signal_db", U8, |r| uint(r.antenna_signal_db.map(|f| f.value))),
+    member!("antenna_noise_db", U8, |r| uint(r.antenna_noise_db.map(|f| f.value))),
+    member!("rx_flags.bad_plcp", Bool, |r| boolean(r.rx_flags.map(|f| f.bad_plcp))),
+    member!("tx_flags.fail", Bool, |r| boolean(r.tx_flags.map(|f| f.fail))),
+    member!("tx_flags.cts", Bool, |r| boolean(r.tx_flags.map(|f| f.cts))),
+    member!("tx_flags.rts", Bool, |r| boolean(r.tx_flags.map(|f| f.rts))),
+    member!("tx_flags.no_ack", Bool, |r| boolean(r.tx_flags.map(|f| f.no_ack))),
+    member!("tx_flags.no_seq", Bool, |r| boolean(r.tx_flags.map(|f| f.no_seq))),
+    member!("rts_retries", U8, |r| uint(r.rts_retries.map(|f| f.value))),
+    member!("data_retries", U8, |r| uint(r.data_retries.map(|f| f.value))),
+    member!("xchannel.flags.turbo", Bool, |r| boolean(r.xchannel.map(|f| f.flags.turbo))),
+    member!("xchannel.flags.cck", Bool, |r| boolean(r.xchannel.map(|f| f.flags.cck))),
+    member!("xchannel.flags.ofdm", Bool, |r| boolean(r.xchannel.map(|f| f.flags.ofdm))),
+    member!("xchannel.flags.ghz2", Bool, |r| boolean(r.xchannel.map(|f| f.flags.ghz2))),
+    member!("xchannel.flags.ghz5", Bool, |r| boolean(r.xchannel.map(|f| f.flags.ghz5))),
+    member!("xchannel.flags.passive", Bool, |r| boolean(r.xchannel.map(|f| f.flags.passive))),
+    member!("xchannel.flags.dynamic", Bool, |r| boolean(r.xchannel.map(|f| f.flags.dynamic))),
+    member!("xchannel.flags.gfsk", Bool, |r| boolean(r.xchannel.map(|f| f.flags.gfsk))),
+    member!("xchannel.flags.gsm", Bool, |r| boolean(r.xchannel.map(|f| f.flags.gsm))),
+    member!("xchannel.flags.sturbo", Bool, |r| boolean(r.xchannel.map(|f| f.flags.sturbo))),
+    member!("xchannel.flags.half", Bool, |r| boolean(r.xchannel.map(|f| f.flags.half))),
+    member!("xchannel.flags.quarter", Bool, |r| boolean(r.xchannel.map(|f| f.flags.quarter))),
+    member!("xchannel.flags.ht20", Bool, |r| boolean(r.xchannel.map(|f| f.flags.ht20))),
+    member!("xchannel.flags.ht40u", Bool, |r| boolean(r.xchannel.map(|f| f.flags.ht40u))),
+    member!("xchannel.flags.ht40d", Bool, |r| boolean(r.xchannel.map(|f| f.flags.ht40d))),
+    member!("xchannel.freq", U16, |r| uint(r.xchannel.map(|f| f.freq))),
+    member!("xchannel.channel", U8, |r| uint(r.xchannel.map(|f| f.channel))),
+    member!("xchannel.max_power", U8, |r| uint(r.xchannel.map(|f| f.max_power))),
+    member!("mcs.bw.bandwidth", U8, |r| uint(mcs_bw(r).map(|bw| bw.bandwidth))),
+    member!("mcs.bw.sideband", U8, |r| uint(mcs_bw(r).and_then(|bw| bw.sideband))),
+    member!("mcs.bw.sideband_index", U8, |r| uint(mcs_bw(r).and_then(|bw| bw.sideband_index))),
+    member!("mcs.index", U8, |r| uint(r.mcs.and_then(|f| f.index))),
+    member!("mcs.gi", Str, |r| name(r.mcs.and_then(|f| f.gi))),
+    member!("mcs.format", Str, |r| name(r.mcs.and_then(|f| f.format))),
+    member!("mcs.fec", Str, |r| name(r.mcs.and_then(|f| f.fec))),
+    member!("mcs.stbc", U8, |r| uint(r.mcs.and_then(|f| f.stbc))),
+    member!("mcs.ness", U8, |r| uint(r.mcs.and_then(|f| f.ness))),
+    member!("mcs.datarate", F32, |r| float(r.mcs.and_then(|f| f.datarate))),
+    member!("ampdu_status.reference", U32, |r| uint(r.ampdu_status.map(|f| f.reference))),
+    member!("ampdu_status.zero_length", Bool, |r| boolean(r.ampdu_status.and_then(|f| f.zero_length))),
+    member!("ampdu_status.last", Bool, |r| boolean(r.ampdu_status.and_then(|f| f.last))),
+    member!("ampdu_status.delimiter_crc", U8, |r| uint(r.ampdu_status.and_then(|f| f.delimiter_crc))),
+    member!("vht.stbc", Bool, |r| boolean(r.vht.and_then(|f| f.stbc))),
+    member!("vht.txop_ps", Bool, |r| boolean(r.vht.and_then(|f| f.txop_ps))),
+    member!("vht.gi", Str, |r| name(r.vht.and_then(|f| f.gi))),
+    member!("vht.sgi_nsym_da", Bool, |r| boolean(r.vht.and_then(|f| f.sgi_nsym_da))),
+    member!("vht.ldpc_extra", Bool, |r| boolean(r.vht.and_then(|f| f.ldpc_extra))),
+    member!("vht.beamformed", Bool, |r| boolean(r.vht.and_then(|f| f.beamformed))),
+    member!("vht.bw.bandwidth", U8, |r| uint(vht_bw(r).map(|bw| bw.bandwidth))),
+    member!("vht.bw.sideband", U8, |r| uint(vht_bw(r).and_then(|bw| bw.sideband))),
+    member!("vht.bw.sideband_index", U8, |r| uint(vht_bw(r).and_then(|bw| bw.sideband_index))),
+    member!("vht.group_id", U8, |r| uint(r.vht.and_then(|f| f.group_id))),
+    member!("vht.partial_aid", U16, |r| uint(r.vht.and_then(|f| f.partial_aid))),
+    member!("vht.users[0].index", U8, user_index::<0>),
+    member!("vht.users[0].fec", Str, user_fec::<0>),
+    member!("vht.users[0].nss", U8, user_nss::<0>),
+    member!("vht.users[0].nsts", U8, user_nsts::<0>),
+    member!("vht.users[0].datarate", F32, user_datarate::<0>),
+    member!("vht.users[1].index", U8, user_index::<1>),
+    member!("vht.users[1].fec", Str, user_fec::<1>),
+    member!("vht.users[1].nss", U8, user_nss::<1>),
+    member!("vht.users[1].nsts", U8, user_nsts::<1>),
+    member!("vht.users[1].datarate", F32, user_datarate::<1>),
+    member!("vht.users[2].index", U8, user_index::<2>),
+    member!("vht.users[2].fec", Str, user_fec::<2>),
+    member!("vht.users[2].nss", U8, user_nss::<2>),
+    member!("vht.users[2].nsts", U8, user_nsts::<2>),
+    member!("vht.users[2].datarate", F32, user_datarate::<2>),
+    member!("vht.users[3].index", U8, user_index::<3>),
+    member!("vht.users[3].fec", Str, user_fec::<3>),
+    member!("vht.users[3].nss", U8, user_nss::<3>),
+    member!("vht.users[3].nsts", U8, user_nsts::<3>),
+    member!("vht.users[3].datarate", F32, user_datarate::<3>),
+    member!("timestamp.timestamp", U64, |r| uint(r.timestamp.map(|f| f.timestamp))),
+    member!("timestamp.unit", Str, |r| name(r.timestamp.map(|f| f.unit))),
+    member!("timestamp.position", Str, |r| name(r.timestamp.map(|f| f.position))),
+    member!("timestamp.accuracy", U16, |r| uint(r.timestamp.and_then(|f| f.accuracy))),
+];
+
+pub(crate) type Presence = fn(&Radiotap) -> bool;
+
+/// The fields and struct members that have members of their own.
+#[rustfmt::skip]
+const STRUCTS: &[(&str, Presence)] = &[
+    ("flags", |r| r.flags.is_some()),
+    ("channel", |r| r.channel.is_some()),
+    ("channel.flags", |r| r.channel.is_some()),
+    ("fhss", |r| r.fhss.is_some()),
+    ("rx_flags", |r| r.rx_flags.is_some()),
+    ("tx_flags", |r| r.tx_flags.is_some()),
+    ("xchannel", |r| r.xchannel.is_some()),
+    ("xchannel.flags", |r| r.xchannel.is_some()),
+    ("mcs", |r| r.mcs.is_some()),
+    ("mcs.bw", |r| mcs_bw(r).is_some()),
+    ("ampdu_status", |r| r.ampdu_status.is_some()),
+    ("vht", |r| r.vht.is_some()),
+    ("vht.bw", |r| vht_bw(r).is_some()),
+    ("vht.users[0]", |r| user::<0>(r).is_some()),
+    ("vht.users[1]", |r| user::<1>(r).is_some()),
+    ("vht.users[2]", |r| user::<2>(r).is_some()),
+    ("vht.users[3]", |r| user::<3>(r).is_some()),
+    ("timestamp", |r| r.timestamp.is_some()),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structs_cover_members() {
+        for member in members() {
+            let mut path = member.path();
+            while let Some(end) = path.rfind('.') {
+                path = &path[..end];
+                assert!(
+                    is_present(path, &Radiotap::default()).is_some(),
+                    "{} has no presence test",
+                    path
+                );
+            }
+        }
+        for (path, _) in STRUCTS {
+            assert!(
+                member(path).is_none(),
+                "{} is both a member and a struct",
+                path
+            );
+        }
+    }
+}
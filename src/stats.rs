@@ -0,0 +1,391 @@
+//! Signal statistics per transmitter and per antenna.
+//!
+//! Signal and noise values are whole dBm, so each [Distribution] keeps the
+//! count of every value seen and its percentiles are exact. All statistics
+//! can be merged, so captures can be processed on separate threads and the
+//! results combined.
+//!
+//! ```
+//! use radiotap::{stats::Stats, Radiotap};
+//!
+//! # let captures: Vec<Vec<u8>> = Vec::new();
+//! let mut stats = Stats::new();
+//! for capture in &captures {
+//!     stats.add_capture(capture).unwrap();
+//! }
+//!
+//! for (transmitter, stats) in &stats.transmitters {
+//!     println!("{:?} median {:?} dBm", transmitter, stats.signal.percentile(50.0));
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    field::{from_bytes, Antenna, AntennaNoise, AntennaSignal, Kind},
+    Radiotap, RadiotapIterator, Result,
+};
+
+/// The distribution of a value in whole units.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Distribution {
+    counts: BTreeMap<i16, u64>,
+    count: u64,
+    sum: i64,
+}
+
+impl Distribution {
+    pub fn new() -> Distribution {
+        Distribution::default()
+    }
+
+    /// Adds a value to the distribution.
+    pub fn add(&mut self, value: i16) {
+        *self.counts.entry(value).or_insert(0) += 1;
+        self.count += 1;
+        self.sum += i64::from(value);
+    }
+
+    /// Adds all values of another distribution.
+    pub fn merge(&mut self, other: &Distribution) {
+        for (&value, &count) in &other.counts {
+            *self.counts.entry(value).or_insert(0) += count;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+    }
+
+    /// Returns the number of values.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the smallest value.
+    pub fn min(&self) -> Option<i16> {
+        self.counts.keys().next().cloned()
+    }
+
+    /// Returns the largest value.
+    pub fn max(&self) -> Option<i16> {
+        self.counts.keys().next_back().cloned()
+    }
+
+    /// Returns the mean of the values.
+    pub fn mean(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            count => Some(self.sum as f64 / count as f64),
+        }
+    }
+
+    /// Returns the value at the percentile between 0 and 100, using the
+    /// nearest rank.
+    pub fn percentile(&self, percentile: f64) -> Option<i16> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * self.count as f64).ceil() as u64;
+
+        let mut seen = 0;
+        for (&value, &count) in &self.counts {
+            seen += count;
+            if seen >= rank.max(1) {
+                return Some(value);
+            }
+        }
+        self.max()
+    }
+}
+
+/// The rate a frame was transmitted at.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RateKey {
+    /// A legacy rate in units of 500 kbps.
+    Legacy(u8),
+    /// An 802.11n MCS index.
+    Ht(u8),
+    /// An 802.11ac MCS index and number of spatial streams.
+    Vht { index: u8, nss: u8 },
+}
+
+impl RateKey {
+    /// Returns the rate of the frame, from the VHT, MCS, or Rate field, in that
+    /// order.
+    pub fn from_radiotap(radiotap: &Radiotap) -> Option<RateKey> {
+        if let Some(vht) = radiotap.vht {
+            if let Some(user) = vht.users.iter().flatten().next() {
+                return Some(RateKey::Vht {
+                    index: user.index,
+                    nss: user.nss,
+                });
+            }
+        }
+        if let Some(index) = radiotap.mcs.and_then(|mcs| mcs.index) {
+            return Some(RateKey::Ht(index));
+        }
+        radiotap
+            .rate
+            .map(|rate| RateKey::Legacy((rate.value * 2.0) as u8))
+    }
+}
+
+/// The signal statistics of a transmitter or antenna.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SignalStats {
+    /// The number of frames.
+    pub frames: u64,
+    /// The antenna signal in dBm.
+    pub signal: Distribution,
+    /// The antenna noise in dBm.
+    pub noise: Distribution,
+    /// The signal to noise ratio in dB, for frames with both a signal and
+    /// noise.
+    pub snr: Distribution,
+    /// The number of frames at each rate.
+    pub rates: BTreeMap<RateKey, u64>,
+}
+
+impl SignalStats {
+    fn add(&mut self, signal: Option<i8>, noise: Option<i8>, rate: Option<RateKey>) {
+        self.frames += 1;
+        if let Some(signal) = signal {
+            self.signal.add(i16::from(signal));
+        }
+        if let Some(noise) = noise {
+            self.noise.add(i16::from(noise));
+        }
+        if let (Some(signal), Some(noise)) = (signal, noise) {
+            self.snr.add(i16::from(signal) - i16::from(noise));
+        }
+        if let Some(rate) = rate {
+            *self.rates.entry(rate).or_insert(0) += 1;
+        }
+    }
+
+    /// Adds the statistics of other frames.
+    pub fn merge(&mut self, other: &SignalStats) {
+        self.frames += other.frames;
+        self.signal.merge(&other.signal);
+        self.noise.merge(&other.noise);
+        self.snr.merge(&other.snr);
+        for (&rate, &count) in &other.rates {
+            *self.rates.entry(rate).or_insert(0) += count;
+        }
+    }
+}
+
+/// The signal and noise of one antenna chain.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ChainSignal {
+    /// The antenna index, or `None` for the combined signal of all chains.
+    pub antenna: Option<u8>,
+    /// The antenna signal in dBm.
+    pub signal: Option<i8>,
+    /// The antenna noise in dBm.
+    pub noise: Option<i8>,
+}
+
+/// Returns the signal and noise of each antenna chain in a Radiotap capture.
+///
+/// Drivers report each chain in its own Radiotap namespace, so a chain ends
+/// when one of its fields is repeated.
+pub fn chains(capture: &[u8]) -> Result<Vec<ChainSignal>> {
+    let mut chains = Vec::new();
+    let mut chain = ChainSignal::default();
+
+    for result in &RadiotapIterator::from_bytes(capture)? {
+        let (kind, data) = result?;
+        let repeated = match kind {
+            Kind::Antenna => chain.antenna.is_some(),
+            Kind::AntennaSignal => chain.signal.is_some(),
+            Kind::AntennaNoise => chain.noise.is_some(),
+            _ => continue,
+        };
+        if repeated {
+            chains.push(chain);
+            chain = ChainSignal::default();
+        }
+
+        match kind {
+            Kind::Antenna => chain.antenna = Some(from_bytes::<Antenna>(data)?.value),
+            Kind::AntennaSignal => chain.signal = Some(from_bytes::<AntennaSignal>(data)?.value),
+            _ => chain.noise = Some(from_bytes::<AntennaNoise>(data)?.value),
+        }
+    }
+
+    if chain != ChainSignal::default() {
+        chains.push(chain);
+    }
+    Ok(chains)
+}
+
+/// Signal statistics per transmitter and per antenna.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// The statistics of all frames.
+    pub total: SignalStats,
+    /// The statistics per transmitter address.
+    pub transmitters: HashMap<[u8; 6], SignalStats>,
+    /// The statistics per antenna index.
+    pub antennas: BTreeMap<u8, SignalStats>,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats::default()
+    }
+
+    /// Adds a frame with the address of its transmitter, if it has one. The
+    /// antenna is taken from the Antenna field.
+    pub fn add(&mut self, radiotap: &Radiotap, transmitter: Option<[u8; 6]>) {
+        let chain = ChainSignal {
+            antenna: radiotap.antenna.map(|a| a.value),
+            signal: radiotap.antenna_signal.map(|s| s.value),
+            noise: radiotap.antenna_noise.map(|n| n.value),
+        };
+        self.add_chains(radiotap, transmitter, &[chain]);
+    }
+
+    /// Adds a frame with the signal of each of its antenna chains, see
+    /// [chains].
+    pub fn add_chains(
+        &mut self,
+        radiotap: &Radiotap,
+        transmitter: Option<[u8; 6]>,
+        chains: &[ChainSignal],
+    ) {
+        let rate = RateKey::from_radiotap(radiotap);
+        let signal = radiotap.antenna_signal.map(|s| s.value);
+        let noise = radiotap.antenna_noise.map(|n| n.value);
+
+        self.total.add(signal, noise, rate);
+        if let Some(transmitter) = transmitter {
+            self.transmitters
+                .entry(transmitter)
+                .or_default()
+                .add(signal, noise, rate);
+        }
+        for chain in chains {
+            if let Some(antenna) = chain.antenna {
+                self.antennas
+                    .entry(antenna)
+                    .or_default()
+                    .add(chain.signal, chain.noise, rate);
+            }
+        }
+    }
+
+    /// Adds a Radiotap capture, taking the transmitter from the 802.11 frame
+    /// following the Radiotap header.
+    #[cfg(feature = "ieee80211")]
+    pub fn add_capture(&mut self, capture: &[u8]) -> Result<()> {
+        use crate::ieee80211::Frame;
+
+        let (radiotap, rest) = Radiotap::parse(capture)?;
+        let transmitter = Frame::parse(&radiotap, rest)
+            .ok()
+            .and_then(|frame| frame.header.transmitter());
+        self.add_chains(&radiotap, transmitter, &chains(capture)?);
+        Ok(())
+    }
+
+    /// Adds all frames of other statistics.
+    pub fn merge(&mut self, other: &Stats) {
+        self.total.merge(&other.total);
+        for (transmitter, stats) in &other.transmitters {
+            self.transmitters
+                .entry(*transmitter)
+                .or_default()
+                .merge(stats);
+        }
+        for (antenna, stats) in &other.antennas {
+            self.antennas.entry(*antenna).or_default().merge(stats);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::Rate;
+
+    #[test]
+    fn distribution() {
+        let mut distribution = Distribution::new();
+        assert_eq!(distribution.percentile(50.0), None);
+
+        for &value in [-70, -60, -50, -40, -60].iter() {
+            distribution.add(value);
+        }
+        assert_eq!(distribution.min(), Some(-70));
+        assert_eq!(distribution.max(), Some(-40));
+        assert_eq!(distribution.mean(), Some(-56.0));
+        assert_eq!(distribution.percentile(0.0), Some(-70));
+        assert_eq!(distribution.percentile(50.0), Some(-60));
+        assert_eq!(distribution.percentile(90.0), Some(-40));
+    }
+
+    #[test]
+    fn per_antenna_chains() {
+        // The combined signal followed by a namespace for each of two chains
+        let capture = [
+            0, 0, 21, 0, 0x20, 0, 0, 0xa0, 0x20, 0x08, 0, 0xa0, 0x20, 0x08, 0, 0, 0xd8, 0xd6, 0,
+            0xda, 1,
+        ];
+        let chains = chains(&capture).unwrap();
+        assert_eq!(
+            chains,
+            vec![
+                ChainSignal {
+                    antenna: None,
+                    signal: Some(-40),
+                    noise: None,
+                },
+                ChainSignal {
+                    antenna: Some(0),
+                    signal: Some(-42),
+                    noise: None,
+                },
+                ChainSignal {
+                    antenna: Some(1),
+                    signal: Some(-38),
+                    noise: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_across_threads() {
+        let radiotap = |signal, noise| Radiotap {
+            antenna_signal: Some(AntennaSignal { value: signal }),
+            antenna_noise: Some(AntennaNoise { value: noise }),
+            antenna: Some(Antenna { value: 1 }),
+            rate: Some(Rate { value: 24.0 }),
+            ..Default::default()
+        };
+
+        let handles: Vec<_> = (0..2)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    let mut stats = Stats::new();
+                    stats.add(&radiotap(-50 - i, -90), Some([i as u8; 6]));
+                    stats.add(&radiotap(-60, -95), Some([9; 6]));
+                    stats
+                })
+            })
+            .collect();
+
+        let mut stats = Stats::new();
+        for handle in handles {
+            stats.merge(&handle.join().unwrap());
+        }
+
+        assert_eq!(stats.total.frames, 4);
+        assert_eq!(stats.total.snr.max(), Some(40));
+        assert_eq!(stats.transmitters.len(), 3);
+        assert_eq!(stats.transmitters[&[9; 6]].frames, 2);
+        assert_eq!(stats.antennas[&1].signal.min(), Some(-60));
+        assert_eq!(stats.total.rates[&RateKey::Legacy(48)], 4);
+    }
+}
@@ -0,0 +1,437 @@
+//! Hardware timestamps of captured frames.
+//!
+//! The TSFT field holds the MAC timer in microseconds, sampled at the start of
+//! the MPDU, while the Timestamp field has its own unit and sampling position.
+//! Both are normalised to nanoseconds here, and can be moved to the start of
+//! the PPDU so that timestamps sampled at different positions compare.
+//!
+//! A [ClockCorrelator] relates the hardware clock to the host clock of a
+//! capture, so that frames can be given host timestamps without the jitter of
+//! the capture path.
+
+use std::time::Duration;
+
+use crate::{
+    airtime::Ppdu,
+    field::ext::{SamplingPosition, TimeUnit},
+    Radiotap,
+};
+
+/// The field a hardware timestamp was taken from.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TimeSource {
+    /// The TSFT field.
+    Tsft,
+    /// The Timestamp field.
+    Timestamp,
+}
+
+/// A hardware timestamp in nanoseconds.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HwTime {
+    /// The timestamp in nanoseconds of the hardware clock.
+    pub nanos: u64,
+    /// The field the timestamp was taken from.
+    pub source: TimeSource,
+    /// The point in the frame at which the timestamp was sampled.
+    pub position: SamplingPosition,
+}
+
+impl HwTime {
+    /// Returns the timestamp as a duration since the epoch of the hardware
+    /// clock.
+    pub fn to_duration(&self) -> Duration {
+        Duration::from_nanos(self.nanos)
+    }
+
+    /// Returns the timestamp moved to the start of the PPDU, the position
+    /// sampled by [SamplingPosition::StartPLCP], given the PPDU that carried
+    /// the frame.
+    ///
+    /// The end of the MPDU is taken to be the end of the PPDU. Returns `None`
+    /// if the sampling position is unknown or the PPDU would start before the
+    /// epoch of the clock.
+    pub fn to_ppdu_start(&self, ppdu: &Ppdu) -> Option<HwTime> {
+        let offset_us = match self.position {
+            SamplingPosition::StartPLCP => 0,
+            SamplingPosition::StartMPDU => ppdu.preamble_us,
+            SamplingPosition::EndPPDU | SamplingPosition::EndMPDU => ppdu.duration_us,
+            SamplingPosition::Unknown => return None,
+        };
+        Some(HwTime {
+            nanos: self.nanos.checked_sub(u64::from(offset_us) * 1_000)?,
+            source: self.source,
+            position: SamplingPosition::StartPLCP,
+        })
+    }
+}
+
+impl Radiotap {
+    /// Returns the hardware timestamp of the frame, from the Timestamp field or
+    /// otherwise the TSFT field.
+    pub fn hw_time(&self) -> Option<HwTime> {
+        if let Some(timestamp) = self.timestamp {
+            let scale = match timestamp.unit {
+                TimeUnit::Milliseconds => 1_000_000,
+                TimeUnit::Microseconds => 1_000,
+                TimeUnit::Nanoseconds => 1,
+            };
+            return Some(HwTime {
+                nanos: timestamp.timestamp.saturating_mul(scale),
+                source: TimeSource::Timestamp,
+                position: timestamp.position,
+            });
+        }
+
+        self.tsft.map(|tsft| HwTime {
+            nanos: tsft.value.saturating_mul(1_000),
+            source: TimeSource::Tsft,
+            position: SamplingPosition::StartMPDU,
+        })
+    }
+
+    /// Returns the hardware timestamp of the start of the PPDU that carried a
+    /// frame of the given length, see [Radiotap::ppdu].
+    pub fn ppdu_start(&self, len: usize) -> Option<HwTime> {
+        self.hw_time()?.to_ppdu_start(&self.ppdu(len)?)
+    }
+}
+
+/// The largest TSFT value of drivers that only report the lower 32 bits.
+const TSFT_32_WRAP: u64 = 1 << 32;
+
+/// A frame timestamp in the host clock derived from its hardware timestamp.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CorrelatedTime {
+    /// The corrected host timestamp.
+    pub host: Duration,
+    /// The hardware timestamp in nanoseconds, with 32-bit TSFT wraparounds
+    /// undone.
+    pub hw_nanos: u64,
+    /// The index of the segment the frame belongs to, a new segment starts
+    /// whenever the hardware clock is reset.
+    pub segment: usize,
+}
+
+/// A least squares fit of the host clock against the hardware clock, relative
+/// to the first point of a segment to keep the precision of `f64`.
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    hw_origin: u64,
+    host_origin: u64,
+    n: f64,
+    mean_hw: f64,
+    mean_host: f64,
+    cov: f64,
+    var: f64,
+    last_hw: u64,
+    wraps: u64,
+}
+
+impl Segment {
+    fn new(hw: u64, host: u64) -> Segment {
+        Segment {
+            hw_origin: hw,
+            host_origin: host,
+            n: 0.0,
+            mean_hw: 0.0,
+            mean_host: 0.0,
+            cov: 0.0,
+            var: 0.0,
+            last_hw: hw,
+            wraps: 0,
+        }
+    }
+
+    /// Returns the slope of the host clock against the hardware clock.
+    fn slope(&self) -> f64 {
+        if self.var > 0.0 {
+            self.cov / self.var
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns the host time in nanoseconds relative to the origin predicted
+    /// for a hardware time.
+    fn predict(&self, hw: u64) -> f64 {
+        let x = hw as f64 - self.hw_origin as f64;
+        self.mean_host + self.slope() * (x - self.mean_hw)
+    }
+
+    fn add(&mut self, hw: u64, host: u64) {
+        let x = hw as f64 - self.hw_origin as f64;
+        let y = host as f64 - self.host_origin as f64;
+
+        // Welford's online update of the means and the co-moments
+        self.n += 1.0;
+        let dx = x - self.mean_hw;
+        self.mean_hw += dx / self.n;
+        self.mean_host += (y - self.mean_host) / self.n;
+        self.var += dx * (x - self.mean_hw);
+        self.cov += dx * (y - self.mean_host);
+        self.last_hw = self.last_hw.max(hw);
+    }
+}
+
+/// Correlates the hardware timestamps of frames with the host timestamps of
+/// a capture.
+///
+/// The host clock is fitted as a linear function of the hardware clock, which
+/// accounts for both the offset and the drift between them. A hardware time
+/// that is further than the tolerance from the fit is treated as a reset of
+/// the hardware clock and starts a new segment, unless it is explained by the
+/// wraparound of a 32-bit TSFT. Frames that are slightly out of order stay in
+/// the current segment.
+///
+/// ```
+/// use radiotap::{field::TSFT, time::ClockCorrelator, Radiotap};
+/// use std::time::Duration;
+///
+/// let mut correlator = ClockCorrelator::new();
+/// for i in 0..10 {
+///     let radiotap = Radiotap {
+///         tsft: Some(TSFT { value: 1_000 * i }),
+///         ..Default::default()
+///     };
+///     let host = Duration::from_millis(5_000 + i);
+///     let time = correlator.push(host, &radiotap).unwrap();
+///     assert_eq!(time.host, host);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClockCorrelator {
+    tolerance: Duration,
+    segment: Option<Segment>,
+    segments: usize,
+}
+
+impl Default for ClockCorrelator {
+    fn default() -> ClockCorrelator {
+        ClockCorrelator::with_tolerance(Duration::from_secs(1))
+    }
+}
+
+impl ClockCorrelator {
+    /// Returns a correlator with a tolerance of one second.
+    pub fn new() -> ClockCorrelator {
+        ClockCorrelator::default()
+    }
+
+    /// Returns a correlator that detects clock resets larger than the given
+    /// tolerance. The tolerance should exceed the jitter of the host
+    /// timestamps.
+    pub fn with_tolerance(tolerance: Duration) -> ClockCorrelator {
+        ClockCorrelator {
+            tolerance,
+            segment: None,
+            segments: 0,
+        }
+    }
+
+    /// Adds a frame with the host time it was captured at, and returns its
+    /// corrected host time. Returns `None` if the frame has no hardware
+    /// timestamp.
+    pub fn push(&mut self, host: Duration, radiotap: &Radiotap) -> Option<CorrelatedTime> {
+        let time = radiotap.hw_time()?;
+        let host = host.as_nanos() as u64;
+        let tolerance = self.tolerance.as_nanos() as f64;
+
+        let mut hw = time.nanos;
+        let continues = match &mut self.segment {
+            Some(segment) => {
+                hw += segment.wraps * TSFT_32_WRAP * 1_000;
+                let near = |hw| {
+                    (segment.predict(hw) - (host as f64 - segment.host_origin as f64)).abs()
+                        <= tolerance
+                };
+
+                // A frame slightly out of order steps back within the tolerance
+                if segment.last_hw as f64 - hw as f64 <= tolerance && near(hw) {
+                    true
+                } else if time.source == TimeSource::Tsft
+                    && time.nanos < TSFT_32_WRAP * 1_000
+                    && near(hw + TSFT_32_WRAP * 1_000)
+                {
+                    segment.wraps += 1;
+                    hw += TSFT_32_WRAP * 1_000;
+                    true
+                } else {
+                    false
+                }
+            }
+            None => false,
+        };
+
+        if !continues {
+            hw = time.nanos;
+            if self.segment.is_some() {
+                self.segments += 1;
+            }
+            self.segment = Some(Segment::new(hw, host));
+        }
+
+        let segment = self.segment.as_mut().unwrap();
+        segment.add(hw, host);
+        let corrected = segment.host_origin as f64 + segment.predict(hw);
+
+        Some(CorrelatedTime {
+            host: Duration::from_nanos(corrected.round().max(0.0) as u64),
+            hw_nanos: hw,
+            segment: self.segments,
+        })
+    }
+
+    /// Returns the drift of the host clock against the hardware clock in the
+    /// current segment, in parts per million.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        let segment = self.segment.as_ref()?;
+        Some((segment.slope() - 1.0) * 1e6)
+    }
+
+    /// Returns the offset of the host clock from the hardware clock in the
+    /// current segment, that is the host time at which the hardware clock
+    /// would have read zero.
+    pub fn offset(&self) -> Option<Duration> {
+        let segment = self.segment.as_ref()?;
+        let offset = segment.host_origin as f64 + segment.predict(0);
+        Some(Duration::from_nanos(offset.round().max(0.0) as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::*;
+
+    #[test]
+    fn tsft() {
+        let radiotap = Radiotap {
+            tsft: Some(TSFT { value: 1_500 }),
+            ..Default::default()
+        };
+
+        let time = radiotap.hw_time().unwrap();
+        assert_eq!(time.nanos, 1_500_000);
+        assert_eq!(time.source, TimeSource::Tsft);
+        assert_eq!(time.position, SamplingPosition::StartMPDU);
+        assert_eq!(time.to_duration(), Duration::from_micros(1_500));
+    }
+
+    #[test]
+    fn timestamp_preferred() {
+        let radiotap = Radiotap {
+            tsft: Some(TSFT { value: 1_500 }),
+            timestamp: Some(Timestamp {
+                timestamp: 3,
+                unit: TimeUnit::Milliseconds,
+                position: SamplingPosition::EndPPDU,
+                accuracy: None,
+            }),
+            ..Default::default()
+        };
+
+        let time = radiotap.hw_time().unwrap();
+        assert_eq!(time.nanos, 3_000_000);
+        assert_eq!(time.source, TimeSource::Timestamp);
+        assert_eq!(time.position, SamplingPosition::EndPPDU);
+        assert_eq!(Radiotap::default().hw_time(), None);
+    }
+
+    fn with_tsft(value: u64) -> Radiotap {
+        Radiotap {
+            tsft: Some(TSFT { value }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn correlate_drift_and_reset() {
+        let mut correlator = ClockCorrelator::new();
+
+        // The host clock runs 100 ppm fast and jitters by up to 9 us
+        for i in 0..100u64 {
+            let jitter = (i * 37 % 10) * 1_000;
+            let host = Duration::from_nanos(7_000_000_000 + i * 100_010_000 + jitter);
+            let time = correlator
+                .push(host, &with_tsft(1_000_000 + i * 100_000))
+                .unwrap();
+            assert_eq!(time.segment, 0);
+        }
+        assert!((correlator.drift_ppm().unwrap() - 100.0).abs() < 1.0);
+        let offset = correlator.offset().unwrap().as_nanos() as f64;
+        assert!((offset - 5_999_900_000.0).abs() < 50_000.0, "{}", offset);
+
+        // The interface was restarted, resetting the TSF
+        let host = Duration::from_secs(9);
+        let time = correlator.push(host, &with_tsft(500)).unwrap();
+        assert_eq!(time.segment, 1);
+        assert_eq!(time.host, host);
+        assert_eq!(correlator.drift_ppm(), Some(0.0));
+    }
+
+    #[test]
+    fn correlate_wraparound() {
+        let mut correlator = ClockCorrelator::new();
+        let last = TSFT_32_WRAP - 1_000;
+
+        correlator.push(Duration::from_secs(100), &with_tsft(last - 1_000));
+        correlator.push(Duration::from_nanos(100_001_000_000), &with_tsft(last));
+        let time = correlator
+            .push(Duration::from_nanos(100_003_000_000), &with_tsft(1_000))
+            .unwrap();
+
+        assert_eq!(time.segment, 0);
+        assert_eq!(time.hw_nanos, (TSFT_32_WRAP + 1_000) * 1_000);
+        assert_eq!(time.host, Duration::from_nanos(100_003_000_000));
+        assert_eq!(
+            correlator.push(Duration::from_secs(100), &Radiotap::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn correlate_reordered() {
+        let mut correlator = ClockCorrelator::new();
+
+        for (i, tsft) in [1_000, 2_000, 4_000, 3_000, 5_000].iter().enumerate() {
+            let host = Duration::from_millis(10_000 + *tsft / 1_000);
+            let time = correlator.push(host, &with_tsft(*tsft)).unwrap();
+            assert_eq!(time.segment, 0, "frame {}", i);
+            assert_eq!(time.host, host);
+        }
+    }
+
+    #[test]
+    fn ppdu_start() {
+        let ppdu = Ppdu {
+            preamble_us: 20,
+            duration_us: 244,
+        };
+        let time = |position| HwTime {
+            nanos: 1_000_000,
+            source: TimeSource::Timestamp,
+            position,
+        };
+
+        let start = time(SamplingPosition::StartMPDU).to_ppdu_start(&ppdu);
+        assert_eq!(start.unwrap().nanos, 980_000);
+        assert_eq!(start.unwrap().position, SamplingPosition::StartPLCP);
+        let start = time(SamplingPosition::EndPPDU).to_ppdu_start(&ppdu);
+        assert_eq!(start.unwrap().nanos, 756_000);
+        let start = time(SamplingPosition::StartPLCP).to_ppdu_start(&ppdu);
+        assert_eq!(start.unwrap().nanos, 1_000_000);
+        assert_eq!(time(SamplingPosition::Unknown).to_ppdu_start(&ppdu), None);
+    }
+
+    #[test]
+    fn parse_position() {
+        // Timestamp in microseconds sampled at the end of the PPDU
+        let mut data = 5u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&[0, 0, 0x21, 0]);
+
+        let timestamp: Timestamp = from_bytes(&data).unwrap();
+        assert_eq!(timestamp.unit, TimeUnit::Microseconds);
+        assert_eq!(timestamp.position, SamplingPosition::EndPPDU);
+    }
+}
@@ -0,0 +1,228 @@
+//! Units for the power and signal fields.
+//!
+//! ```
+//! use radiotap::units::{Db, Dbm};
+//!
+//! let signal = Dbm(-40.0);
+//! assert_eq!(signal - Dbm(-90.0), Db(50.0));
+//! assert_eq!(signal.to_milliwatt().0, 0.0001);
+//! ```
+
+use std::{
+    fmt,
+    ops::{Add, Sub},
+};
+
+use crate::{
+    field::{
+        AntennaNoise, AntennaNoiseDb, AntennaSignal, AntennaSignalDb, TxAttenuationDb, TxPower,
+    },
+    Radiotap,
+};
+
+/// An absolute power in decibels relative to one milliwatt.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Dbm(pub f64);
+
+/// A power ratio in decibels.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Db(pub f64);
+
+/// An absolute power in milliwatts.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct MilliWatt(pub f64);
+
+impl Dbm {
+    /// Returns the power in milliwatts.
+    pub fn to_milliwatt(self) -> MilliWatt {
+        MilliWatt(10f64.powf(self.0 / 10.0))
+    }
+}
+
+impl MilliWatt {
+    /// Returns the power in dBm.
+    pub fn to_dbm(self) -> Dbm {
+        Dbm(10.0 * self.0.log10())
+    }
+}
+
+impl From<Dbm> for MilliWatt {
+    fn from(dbm: Dbm) -> MilliWatt {
+        dbm.to_milliwatt()
+    }
+}
+
+impl From<MilliWatt> for Dbm {
+    fn from(mw: MilliWatt) -> Dbm {
+        mw.to_dbm()
+    }
+}
+
+impl Sub for Dbm {
+    type Output = Db;
+
+    fn sub(self, other: Dbm) -> Db {
+        Db(self.0 - other.0)
+    }
+}
+
+impl Add<Db> for Dbm {
+    type Output = Dbm;
+
+    fn add(self, db: Db) -> Dbm {
+        Dbm(self.0 + db.0)
+    }
+}
+
+impl Sub<Db> for Dbm {
+    type Output = Dbm;
+
+    fn sub(self, db: Db) -> Dbm {
+        Dbm(self.0 - db.0)
+    }
+}
+
+impl Add for Db {
+    type Output = Db;
+
+    fn add(self, other: Db) -> Db {
+        Db(self.0 + other.0)
+    }
+}
+
+impl Sub for Db {
+    type Output = Db;
+
+    fn sub(self, other: Db) -> Db {
+        Db(self.0 - other.0)
+    }
+}
+
+impl Add for MilliWatt {
+    type Output = MilliWatt;
+
+    fn add(self, other: MilliWatt) -> MilliWatt {
+        MilliWatt(self.0 + other.0)
+    }
+}
+
+impl fmt::Display for Dbm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} dBm", self.0)
+    }
+}
+
+impl fmt::Display for Db {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} dB", self.0)
+    }
+}
+
+impl fmt::Display for MilliWatt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} mW", self.0)
+    }
+}
+
+/// Returns the total power of the chains of a receiver, the sum of their
+/// powers in milliwatts. Returns `None` if there are no chains.
+pub fn combined(chains: impl IntoIterator<Item = Dbm>) -> Option<Dbm> {
+    chains
+        .into_iter()
+        .map(Dbm::to_milliwatt)
+        .fold(None, |total, mw| Some(total.map_or(mw, |total| total + mw)))
+        .map(MilliWatt::to_dbm)
+}
+
+impl AntennaSignal {
+    /// Returns the signal power.
+    pub fn dbm(&self) -> Dbm {
+        Dbm(f64::from(self.value))
+    }
+}
+
+impl AntennaNoise {
+    /// Returns the noise power.
+    pub fn dbm(&self) -> Dbm {
+        Dbm(f64::from(self.value))
+    }
+}
+
+impl AntennaSignalDb {
+    /// Returns the signal power above the arbitrary reference.
+    pub fn db(&self) -> Db {
+        Db(f64::from(self.value))
+    }
+}
+
+impl AntennaNoiseDb {
+    /// Returns the noise power above the arbitrary reference.
+    pub fn db(&self) -> Db {
+        Db(f64::from(self.value))
+    }
+}
+
+impl TxPower {
+    /// Returns the transmit power.
+    pub fn dbm(&self) -> Dbm {
+        Dbm(f64::from(self.value))
+    }
+}
+
+impl TxAttenuationDb {
+    /// Returns the attenuation below the maximum transmit power.
+    pub fn db(&self) -> Db {
+        Db(f64::from(self.value))
+    }
+}
+
+impl Radiotap {
+    /// Returns the signal to noise ratio, from the dBm signal and noise fields
+    /// or otherwise the dB signal and noise fields.
+    pub fn snr_db(&self) -> Option<Db> {
+        if let (Some(signal), Some(noise)) = (self.antenna_signal, self.antenna_noise) {
+            return Some(signal.dbm() - noise.dbm());
+        }
+        match (self.antenna_signal_db, self.antenna_noise_db) {
+            (Some(signal), Some(noise)) => Some(signal.db() - noise.db()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        assert_eq!(Dbm(20.0).to_milliwatt(), MilliWatt(100.0));
+        assert_eq!(MilliWatt::from(Dbm(0.0)), MilliWatt(1.0));
+        assert_eq!(Dbm::from(MilliWatt(1000.0)), Dbm(30.0));
+        assert_eq!(Dbm(-60.0) + Db(3.0), Dbm(-57.0));
+        assert_eq!(Dbm(-60.0).to_string(), "-60 dBm");
+    }
+
+    #[test]
+    fn combined_chains() {
+        let total = combined(vec![Dbm(-50.0), Dbm(-50.0)]).unwrap();
+        assert!((total.0 - -46.9897).abs() < 1e-4);
+        assert_eq!(combined(vec![Dbm(-42.0)]), Some(Dbm(-42.0)));
+        assert_eq!(combined(Vec::new()), None);
+    }
+
+    #[test]
+    fn snr() {
+        let mut radiotap = Radiotap {
+            antenna_signal_db: Some(AntennaSignalDb { value: 40 }),
+            antenna_noise_db: Some(AntennaNoiseDb { value: 10 }),
+            ..Default::default()
+        };
+        assert_eq!(radiotap.snr_db(), Some(Db(30.0)));
+
+        radiotap.antenna_signal = Some(AntennaSignal { value: -45 });
+        radiotap.antenna_noise = Some(AntennaNoise { value: -95 });
+        assert_eq!(radiotap.snr_db(), Some(Db(50.0)));
+        assert_eq!(Radiotap::default().snr_db(), None);
+    }
+}